@@ -0,0 +1,212 @@
+use std::{
+    fs,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{
+    Target,
+    platform::{KnownFolder, Platform},
+    rules::RuleList,
+};
+
+// Linux 上的浏览器缓存：从 Chromium 系浏览器的 Local State 和 Firefox 的 profiles.ini 找出所有配置文件，
+// 只清理其中真正的缓存目录。浏览器正在运行时（锁文件指向的进程存在）整个配置文件跳过
+
+// Chromium 系浏览器的目标名称和配置目录（$XDG_CONFIG_HOME 与 $XDG_CACHE_HOME 下同名）
+const CHROMIUM_BROWSERS: &[(&str, &str)] = &[
+    ("chrome", "google-chrome"),
+    ("chrome", "google-chrome-beta"),
+    ("chrome", "google-chrome-unstable"),
+    ("chromium", "chromium"),
+    ("edge", "microsoft-edge"),
+    ("edge", "microsoft-edge-beta"),
+    ("brave", "BraveSoftware/Brave-Browser"),
+    ("vivaldi", "vivaldi"),
+];
+
+// 缓存目录本身保留，只清理其中的内容
+const CHROMIUM_PROFILE_CACHES: &[&str] = &["/Cache/*", "/Code Cache/*", "/GPUCache/*"];
+const CHROMIUM_SHARED_CACHES: &[&str] = &["/GrShaderCache/*", "/ShaderCache/*", "/GraphiteDawnCache/*"];
+const FIREFOX_PROFILE_CACHES: &[&str] = &["/cache2/*", "/startupCache/*"];
+
+pub fn targets(platform: &dyn Platform) -> Vec<Target> {
+    let (Some(config_home), Some(cache_home)) =
+        (platform.known_folder(KnownFolder::Config), platform.known_folder(KnownFolder::Cache))
+    else {
+        return Vec::new();
+    };
+
+    let mut targets = Vec::new();
+    for (name, dir) in CHROMIUM_BROWSERS {
+        chromium_targets(name, &config_home.join(dir), &cache_home.join(dir), &mut targets);
+    }
+
+    // 较新的 Firefox 遵循 XDG 目录，旧版本使用 ~/.mozilla
+    let mut firefox_dirs = vec![config_home.join("mozilla").join("firefox")];
+    firefox_dirs.extend(platform.known_folder(KnownFolder::Home).map(|home| home.join(".mozilla").join("firefox")));
+    for dir in firefox_dirs {
+        firefox_targets(&dir, &cache_home.join("mozilla").join("firefox"), &mut targets);
+    }
+    targets
+}
+
+// Local State 中 profile.info_cache 的键是各配置文件的目录名；整个浏览器共用一个 SingletonLock
+fn chromium_targets(name: &str, user_data: &Path, cache: &Path, targets: &mut Vec<Target>) {
+    let Ok(content) = fs::read_to_string(user_data.join("Local State")) else {
+        return;
+    };
+    let Ok(local_state) = serde_json::from_str::<serde_json::Value>(&content) else {
+        return;
+    };
+    let profiles: Vec<String> = local_state
+        .pointer("/profile/info_cache")
+        .and_then(|info_cache| info_cache.as_object())
+        .map(|info_cache| info_cache.keys().cloned().collect())
+        .unwrap_or_else(|| vec!["Default".to_string()]);
+
+    let lock = user_data.join("SingletonLock");
+    let rule = format!("{}-cache", name);
+    let target = |path: PathBuf, patterns: &[&str]| {
+        Target::new(name, path)
+            .with_rules(RuleList::builtin(&[(&rule, patterns)]))
+            .with_category("浏览器缓存")
+            .with_lock(lock.clone())
+    };
+
+    targets.push(target(user_data.to_path_buf(), CHROMIUM_SHARED_CACHES));
+    for profile in profiles.iter().filter(|profile| is_plain_name(profile)) {
+        targets.push(target(user_data.join(profile), CHROMIUM_PROFILE_CACHES));
+        targets.push(target(cache.join(profile), CHROMIUM_PROFILE_CACHES));
+    }
+}
+
+// profiles.ini 中每个 [ProfileN] 段的 Path 是配置文件目录，IsRelative=1（默认）时相对于 profiles.ini 所在目录，
+// 对应的缓存在 $XDG_CACHE_HOME/mozilla/firefox 下的同名目录。每个配置文件有自己的 lock
+fn firefox_targets(firefox_dir: &Path, cache: &Path, targets: &mut Vec<Target>) {
+    let Ok(content) = fs::read_to_string(firefox_dir.join("profiles.ini")) else {
+        return;
+    };
+
+    for (path, is_relative) in parse_profiles_ini(&content) {
+        let (profile_dir, cache_dir) = if is_relative {
+            if !Path::new(&path).components().all(|component| matches!(component, Component::Normal(_))) {
+                continue;
+            }
+            (firefox_dir.join(&path), Some(cache.join(&path)))
+        } else {
+            (PathBuf::from(&path), None)
+        };
+        if !profile_dir.is_absolute() {
+            continue;
+        }
+
+        let lock = profile_dir.join("lock");
+        let target = |path: PathBuf| {
+            Target::new("firefox", path)
+                .with_rules(RuleList::builtin(&[("firefox-cache", FIREFOX_PROFILE_CACHES)]))
+                .with_category("浏览器缓存")
+                .with_lock(lock.clone())
+        };
+        targets.push(target(profile_dir.clone()));
+        targets.extend(cache_dir.map(target));
+    }
+}
+
+// 返回各配置文件的 (Path, IsRelative)
+fn parse_profiles_ini(content: &str) -> Vec<(String, bool)> {
+    let mut profiles = Vec::new();
+    let mut current: Option<(Option<String>, bool)> = None;
+
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            if let Some((Some(path), is_relative)) = current.take() {
+                profiles.push((path, is_relative));
+            }
+            if line.starts_with("[Profile") {
+                current = Some((None, true));
+            }
+        } else if let Some((path, is_relative)) = current.as_mut()
+            && let Some((key, value)) = line.split_once('=')
+        {
+            match key.trim() {
+                "Path" => *path = Some(value.trim().to_string()),
+                "IsRelative" => *is_relative = value.trim() != "0",
+                _ => {}
+            }
+        }
+    }
+    if let Some((Some(path), is_relative)) = current {
+        profiles.push((path, is_relative));
+    }
+    profiles
+}
+
+// 配置文件名来自 Local State，只接受单级目录名
+fn is_plain_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::Fake;
+
+    #[test]
+    fn profiles_ini_paths() {
+        let ini = "[General]\nStartWithLastProfile=1\n\n\
+                   [Profile0]\nName=default\nIsRelative=1\nPath=abc.default-release\n\n\
+                   [Profile1]\nName=other\nIsRelative=0\nPath=/data/firefox/other\n\n\
+                   [Profile2]\nName=no-path\n\n\
+                   [Install4F96D1932A9F858E]\nDefault=abc.default-release\n\n\
+                   [Profile3]\nPath = spaced.profile\n";
+        assert_eq!(parse_profiles_ini(ini), [
+            ("abc.default-release".to_string(), true),
+            ("/data/firefox/other".to_string(), false),
+            ("spaced.profile".to_string(), true),
+        ]);
+        assert!(parse_profiles_ini("Path=outside-section\n").is_empty());
+    }
+
+    #[test]
+    fn targets_from_platform_folders() {
+        let temp = tempfile::tempdir().unwrap();
+        let (home, config, cache) = (temp.path().join("home"), temp.path().join("config"), temp.path().join("cache"));
+        let chrome = config.join("google-chrome");
+        fs::create_dir_all(&chrome).unwrap();
+        fs::write(chrome.join("Local State"), r#"{"profile":{"info_cache":{"Default":{},"Profile 1":{},"..":{}}}}"#).unwrap();
+
+        let firefox = config.join("mozilla/firefox");
+        fs::create_dir_all(&firefox).unwrap();
+        fs::write(firefox.join("profiles.ini"),
+            "[Profile0]\nPath=abc.default\n[Profile1]\nPath=../escape\n[Profile2]\nIsRelative=0\nPath=relative\n").unwrap();
+        // 旧版本 Firefox 的 ~/.mozilla，绝对路径的配置文件没有对应的缓存目录
+        let legacy = home.join(".mozilla/firefox");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("profiles.ini"), "[Profile0]\nIsRelative=0\nPath=/srv/firefox/work\n").unwrap();
+
+        let platform = Fake {
+            folders: vec![(KnownFolder::Home, home), (KnownFolder::Config, config), (KnownFolder::Cache, cache.clone())],
+            ..Fake::default()
+        };
+        let found: Vec<(String, PathBuf, Option<PathBuf>)> = targets(&platform)
+            .into_iter()
+            .map(|target| (target.name, target.path, target.lock))
+            .collect();
+
+        let chrome_lock = Some(chrome.join("SingletonLock"));
+        let firefox_lock = Some(firefox.join("abc.default/lock"));
+        assert_eq!(found, [
+            ("chrome".to_string(), chrome.clone(), chrome_lock.clone()),
+            ("chrome".to_string(), chrome.join("Default"), chrome_lock.clone()),
+            ("chrome".to_string(), cache.join("google-chrome/Default"), chrome_lock.clone()),
+            ("chrome".to_string(), chrome.join("Profile 1"), chrome_lock.clone()),
+            ("chrome".to_string(), cache.join("google-chrome/Profile 1"), chrome_lock),
+            ("firefox".to_string(), firefox.join("abc.default"), firefox_lock.clone()),
+            ("firefox".to_string(), cache.join("mozilla/firefox/abc.default"), firefox_lock),
+            ("firefox".to_string(), PathBuf::from("/srv/firefox/work"), Some(PathBuf::from("/srv/firefox/work/lock"))),
+        ]);
+
+        // 找不到配置或缓存目录时没有浏览器目标
+        assert!(targets(&Fake::default()).is_empty());
+    }
+}
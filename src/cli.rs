@@ -0,0 +1,393 @@
+use clap::{Parser, Subcommand};
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    PlanEntry, Target,
+    config::{Config, RuleSet, SweepMode},
+    event::{Event, EventSender},
+    journal, quarantine, sweep,
+    inuse::OpenFiles,
+    mounts::Mounts,
+    platform::{KnownFolder, Platform},
+    rules::RuleList,
+    space::FilesystemSpace,
+    build_plan, empty_recycle_bin, format_size, get_cached_targets,
+    plan_directory, plan_log_files, protect_plan, release_memory, report_plan, report_target_totals, run_clean, tree_size,
+};
+
+#[derive(Parser)]
+#[command(name = "diskspace_free", version, about = "Windows / Linux 磁盘空间清理工具，不带子命令时启动终端界面")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// 使用指定的配置文件代替用户级配置文件
+    #[arg(long, value_name = "FILE", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// 只预览将要清理的内容，不删除任何文件
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// 只处理指定名称的清理目标，可重复使用（名称见 report 输出）
+    #[arg(short = 't', long = "target", value_name = "NAME", global = true)]
+    pub targets: Vec<String>,
+
+    /// 跳过指定名称的清理目标，可重复使用
+    #[arg(long = "skip-target", value_name = "NAME", global = true)]
+    pub skip_targets: Vec<String>,
+
+    /// 额外清理的目录，可重复使用；只清理其中超过 7 天未变化的临时文件
+    #[arg(long = "path", value_name = "DIR", global = true)]
+    pub paths: Vec<PathBuf>,
+
+    /// 把要删除的项先移入隔离区，过期后再删除（覆盖配置中的 quarantine.enabled）
+    #[arg(long, global = true)]
+    pub quarantine: bool,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// 扫描所有目标并输出清理计划，不删除任何文件
+    Scan,
+    /// 清理选定的目标
+    Clean {
+        /// 不扫描磁盘上的日志文件
+        #[arg(long)]
+        no_logs: bool,
+        /// 不清空回收站
+        #[arg(long)]
+        no_recycle: bool,
+    },
+    /// 释放进程工作集内存
+    Memory,
+    /// 只扫描并清理磁盘上的日志文件
+    Logs,
+    /// 清空回收站
+    Recycle,
+    /// 按清理目标汇总可释放的空间
+    Report,
+    /// 按机器和启动汇总 systemd 日志的占用，以及按当前策略可删除的归档文件
+    Journal,
+    /// 清理长时间没有构建的 Rust 项目的 target/ 目录，按项目汇总可释放的空间
+    Sweep {
+        /// 在这些目录下查找 Cargo 项目（覆盖 sweep.project_roots）
+        roots: Vec<PathBuf>,
+        /// 只清理超过该天数没有构建的项目（覆盖 sweep.max_idle_days）
+        #[arg(long, value_name = "DAYS")]
+        days: Option<u64>,
+        /// 要清理的内容（覆盖 sweep.mode）
+        #[arg(long, value_enum)]
+        mode: Option<SweepMode>,
+    },
+    /// 把某次运行移入隔离区的内容恢复到原位置；不带参数时列出所有隔离记录
+    Restore {
+        run_id: Option<String>,
+    },
+}
+
+// --path 给出的目录用途未知，只按 temp-files 规则清理超过该天数未变化的文件
+const CUSTOM_PATH_MIN_AGE_DAYS: u64 = 7;
+
+// 根据 --target / --skip-target / --path 选出本次要处理的目标
+pub fn select_targets(cli: &Cli, config: &Config, platform: &dyn Platform) -> Result<Vec<Target>, String> {
+    let all_targets = get_cached_targets(config, platform);
+
+    for name in cli.targets.iter().chain(&cli.skip_targets) {
+        if !all_targets.iter().any(|target| &target.name == name) {
+            let mut names: Vec<&str> = all_targets.iter().map(|target| target.name.as_str()).collect();
+            names.sort_unstable();
+            names.dedup();
+            return Err(format!("未知的清理目标: {} (可用目标: {})", name, names.join(", ")));
+        }
+    }
+
+    let mut targets: Vec<Target> = all_targets
+        .into_iter()
+        .filter(|target| cli.targets.is_empty() || cli.targets.contains(&target.name))
+        .filter(|target| !cli.skip_targets.contains(&target.name))
+        .collect();
+
+    // 只给了 --path 时只清理这些目录
+    if cli.targets.is_empty() && !cli.paths.is_empty() {
+        targets.clear();
+    }
+    for path in &cli.paths {
+        targets.push(custom_target(path, platform.known_folder(KnownFolder::Home).as_deref())?);
+    }
+
+    Ok(targets)
+}
+
+// 根目录、用户主目录和包含主目录的上级目录中都是用户数据，不能作为 --path
+fn custom_target(path: &Path, home: Option<&Path>) -> Result<Target, String> {
+    let resolved = path.canonicalize().map_err(|err| format!("无法访问 {}: {}", path.display(), err))?;
+    let home = home.and_then(|home| home.canonicalize().ok());
+    if resolved.parent().is_none() || home.is_some_and(|home| home.starts_with(&resolved)) {
+        return Err(format!("拒绝清理 {}: 不能把根目录或用户主目录作为 --path", path.display()));
+    }
+    Ok(Target::new("custom", path)
+        .with_rules(RuleList::preset(RuleSet::TempFiles, path))
+        .with_min_age(CUSTOM_PATH_MIN_AGE_DAYS)
+        .with_category("自定义目录"))
+}
+
+// 返回是否没有发生错误（删除、恢复、清空回收站或释放内存失败），用于决定退出码
+pub fn run(command: Command, dry_run: bool, targets: Vec<Target>, config: &Config, platform: &dyn Platform) -> bool {
+    let log_scan = config.log_scan.enabled.then_some(&config.log_scan);
+
+    match command {
+        Command::Scan => with_printer(|sender| {
+            let plan = build_plan(&targets, log_scan, &config.scan, sender.clone());
+            report_plan(&plan, sender);
+        }),
+        Command::Clean { no_logs, no_recycle } => with_printer(|sender| {
+            let plan = build_plan(&targets, log_scan.filter(|_| !no_logs), &config.scan, sender.clone());
+            if dry_run {
+                report_plan(&plan, sender.clone());
+                if !no_recycle {
+                    sender.send(Event::Progress("预览: 将清空回收站 (未执行)".to_string())).ok();
+                }
+                return;
+            }
+
+            run_clean(&plan, !no_recycle, config, platform, sender);
+        }),
+        Command::Memory => {
+            if dry_run {
+                println!("预览: 将释放进程工作集内存 (未执行)");
+                true
+            } else {
+                with_printer(|sender| release_memory(platform, &config.memory, sender))
+            }
+        }
+        Command::Logs => with_printer(|sender| {
+            let plan = build_plan(&[], Some(&config.log_scan), &config.scan, sender.clone());
+            if dry_run {
+                report_plan(&plan, sender);
+            } else {
+                run_clean(&plan, false, config, platform, sender);
+            }
+        }),
+        Command::Recycle => with_printer(|sender| {
+            if dry_run {
+                sender.send(Event::Progress("预览: 将清空回收站 (未执行)".to_string())).ok();
+            } else {
+                empty_recycle_bin(platform, config, sender);
+            }
+        }),
+        Command::Report => {
+            print_report(&targets, config);
+            true
+        }
+        Command::Journal => {
+            print_journal_usage(config);
+            true
+        }
+        Command::Sweep { roots, days, mode } => with_printer(|sender| {
+            let mut sweep_config = config.sweep.clone();
+            if !roots.is_empty() {
+                sweep_config.project_roots = roots;
+            }
+            let max_idle_days = days.unwrap_or(sweep_config.max_idle_days);
+            let mode = mode.unwrap_or(sweep_config.mode);
+
+            let mut targets = Vec::new();
+            for project in sweep::find_projects(&sweep_config) {
+                let message = match project.idle_days() {
+                    Some(days) if project.is_idle(max_idle_days) => {
+                        targets.push(project.target(mode));
+                        format!("{}: {} 天没有构建", project.root.display(), days)
+                    }
+                    Some(days) => format!("跳过 {}: {} 天前构建过", project.root.display(), days),
+                    None => format!("跳过 {}: 无法确定上次构建时间", project.root.display()),
+                };
+                sender.send(Event::Progress(message)).ok();
+            }
+
+            let plan = build_plan(&targets, None, &config.scan, sender.clone());
+            if dry_run {
+                report_plan(&plan, sender);
+            } else {
+                // 删除前先列出每个项目可释放的空间
+                report_target_totals(&plan, &sender);
+                run_clean(&plan, false, config, platform, sender);
+            }
+        }),
+        Command::Restore { run_id: None } => {
+            let runs = quarantine::list_runs(&config.quarantine);
+            if runs.is_empty() {
+                println!("隔离区 {} 中没有记录", config.quarantine.dir.display());
+            }
+            for run in runs {
+                println!("{}  创建于 {} UTC  {} 项  {}", run.run_id,
+                    quarantine::format_run_time(run.created), run.entries, format_size(run.bytes));
+            }
+            true
+        }
+        Command::Restore { run_id: Some(run_id) } => with_printer(|sender| {
+            match quarantine::restore(&config.quarantine, &run_id, sender.clone()) {
+                Ok(count) => sender.send(Event::Progress(format!("恢复完成, 共恢复了 {} 项", count))).ok(),
+                Err(error) => sender.send(Event::RestoreFailed { path: config.quarantine.dir.join(&run_id), error }).ok(),
+            };
+        }),
+    }
+}
+
+fn print_report(targets: &[Target], config: &Config) {
+    println!("{:<16} {:>8} {:>12}  路径", "目标", "项数", "可释放");
+    let open_files = OpenFiles::collect();
+    let mounts = Mounts::load(&config.scan);
+    let (sender, _receiver) = mpsc::channel();
+
+    let mut total_entries = 0;
+    let mut total_size = 0;
+    for target in targets {
+        let plan = protect_plan(plan_directory(target, &mounts), &open_files, &sender);
+        let size: u64 = plan.iter().map(|entry| entry.size).sum();
+        println!("{:<16} {:>8} {:>12}  {}", target.name, plan.len(), format_size(size), target.path.display());
+        if target.breakdown {
+            print_breakdown(target, &plan);
+        }
+        total_entries += plan.len();
+        total_size += size;
+    }
+
+    if config.log_scan.enabled {
+        let plan = protect_plan(plan_log_files(&config.log_scan, &mounts, &open_files, sender.clone()), &open_files, &sender);
+        let size: u64 = plan.iter().map(|entry| entry.size).sum();
+        println!("{:<16} {:>8} {:>12}", "logs", plan.len(), format_size(size));
+        total_entries += plan.len();
+        total_size += size;
+    }
+
+    println!("{:<16} {:>8} {:>12}", "合计", total_entries, format_size(total_size));
+
+    let filesystems = FilesystemSpace::snapshot(targets.iter().map(|target| target.path.as_path()));
+    for fs in filesystems {
+        if let Some(available) = fs.before {
+            println!("文件系统 {} 当前可用 {}", fs.root.display(), format_size(available));
+        }
+    }
+}
+
+fn print_journal_usage(config: &Config) {
+    let dir = &config.journal.dir;
+    let files = journal::scan(dir);
+    if files.is_empty() {
+        println!("{} 中没有 systemd 日志", dir.display());
+        return;
+    }
+
+    let current_machine = journal::current_machine_id();
+    let current_boot = journal::current_boot_id();
+    let mark = |id: &str, current: &Option<String>| if current.as_deref() == Some(id) { " (当前)" } else { "" };
+    let format_time = |time: Option<SystemTime>| {
+        time.and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or_else(|| "-".to_string(), |time| quarantine::format_run_time(time.as_secs()))
+    };
+
+    let mut machine_ids: Vec<&str> = files.iter().map(|file| file.machine_id.as_str()).collect();
+    machine_ids.sort();
+    machine_ids.dedup();
+    for machine_id in machine_ids {
+        let machine_files: Vec<&journal::JournalFile> = files.iter().filter(|file| file.machine_id == machine_id).collect();
+        let size: u64 = machine_files.iter().map(|file| file.size).sum();
+        let archived: u64 = machine_files.iter().filter(|file| file.archived).map(|file| file.size).sum();
+        println!("机器 {}{}  {} 个文件  {} (已归档 {})", machine_id, mark(machine_id, &current_machine),
+            machine_files.len(), format_size(size), format_size(archived));
+
+        // 按最早的记录时间排列各次启动
+        let mut boots: Vec<(Option<&str>, Vec<&journal::JournalFile>)> = Vec::new();
+        for file in machine_files {
+            match boots.iter_mut().find(|(boot_id, _)| *boot_id == file.boot_id.as_deref()) {
+                Some((_, boot_files)) => boot_files.push(file),
+                None => boots.push((file.boot_id.as_deref(), vec![file])),
+            }
+        }
+        boots.sort_by_key(|(_, boot_files)| boot_files.iter().filter_map(|file| file.head).min());
+        for (boot_id, boot_files) in boots {
+            let size: u64 = boot_files.iter().map(|file| file.size).sum();
+            let head = boot_files.iter().filter_map(|file| file.head).min();
+            let tail = boot_files.iter().filter_map(|file| file.tail).max();
+            println!("  启动 {}{}  {} 个文件  {}  {} ~ {} UTC", boot_id.unwrap_or("未知"),
+                mark(boot_id.unwrap_or_default(), &current_boot), boot_files.len(), format_size(size),
+                format_time(head), format_time(tail));
+        }
+    }
+
+    let target = Target::new("journal", dir).with_journal(config.journal.clone());
+    let plan = plan_directory(&target, &Mounts::load(&config.scan));
+    let size: u64 = plan.iter().map(|entry| entry.size).sum();
+    println!("按当前策略 (超过 {} 天, 每台机器最多 {} MB) 可删除 {} 个归档文件, 释放 {}",
+        config.journal.max_age_days, config.journal.max_size_mb, plan.len(), format_size(size));
+}
+
+// 按第一级子目录列出当前占用和计划可释放的空间，占用大的在前
+fn print_breakdown(target: &Target, plan: &[PlanEntry]) {
+    let Ok(entries) = fs::read_dir(&target.path) else {
+        return;
+    };
+    let mut dirs: Vec<(String, u64, u64)> = entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .map(|entry| {
+            let path = entry.path();
+            let reclaimable = plan.iter().filter(|item| item.path.starts_with(&path)).map(|item| item.size).sum();
+            (entry.file_name().to_string_lossy().into_owned(), tree_size(&path), reclaimable)
+        })
+        .collect();
+    dirs.sort_by_key(|(_, size, _)| std::cmp::Reverse(*size));
+
+    for (name, size, reclaimable) in dirs {
+        println!("  {:<24} 占用 {:>10}  可释放 {:>10}", name, format_size(size), format_size(reclaimable));
+    }
+}
+
+// 在后台线程中把事件逐行打印出来（错误输出到标准错误），适合脚本和无终端环境；
+// 返回是否没有收到错误事件
+fn with_printer(work: impl FnOnce(EventSender)) -> bool {
+    let (sender, receiver) = mpsc::channel::<Event>();
+    let printer = thread::spawn(move || {
+        let mut succeeded = true;
+        for event in receiver {
+            if event.is_error() {
+                succeeded = false;
+                eprintln!("{}", event);
+            } else {
+                println!("{}", event);
+            }
+        }
+        succeeded
+    });
+
+    work(sender);
+    printer.join().unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::Fake;
+
+    #[test]
+    fn unknown_target_lists_each_name_once() {
+        // 配置中的目标与内置的 tmp 同名，两者在目标列表中不相邻
+        let config: Config = toml::from_str("[[targets]]\nname = \"tmp\"\npath = \"/var/tmp\"\n").unwrap();
+        let cli = Cli::try_parse_from(["diskspace_free", "--target", "nope"]).unwrap();
+        let Err(message) = select_targets(&cli, &config, &Fake::default()) else {
+            panic!("未知目标应当报错");
+        };
+        let names = message.split_once("可用目标: ").unwrap().1.trim_end_matches(')');
+        let names: Vec<&str> = names.split(", ").collect();
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]), "{}", message);
+        assert!(names.contains(&"tmp"));
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    ffi::OsString,
+    fs::{File, FileTimes},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use flate2::{Compression, read::GzDecoder, write::GzEncoder};
+
+use crate::{config::CompressFormat, rooted};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+impl CompressFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            CompressFormat::Gz => ".gz",
+            CompressFormat::Zst => ".zst",
+        }
+    }
+}
+
+// 把根目录内的文件原地压缩为同目录下的 <名称>.gz 或 <名称>.zst：保留原文件的权限、所有者和访问/修改时间，
+// 解压校验内容一致后才删除原文件；返回压缩后的大小。同名的压缩文件已存在时失败，不会覆盖
+pub fn compress(root: &Path, path: &Path, format: CompressFormat) -> io::Result<u64> {
+    let dest = with_suffix(path, format.extension());
+    let mut source = rooted::open_file(root, path)?;
+    let metadata = source.metadata()?;
+    let output = rooted::create_file(root, &dest)?;
+
+    let written = (|| {
+        let output = encode(&mut source, output, format)?;
+        output.sync_all()?;
+        verify(&mut source, rooted::open_file(root, &dest)?, format)?;
+
+        copy_owner(&output, &metadata);
+        output.set_permissions(metadata.permissions())?;
+        let mut times = FileTimes::new();
+        if let Ok(accessed) = metadata.accessed() {
+            times = times.set_accessed(accessed);
+        }
+        if let Ok(modified) = metadata.modified() {
+            times = times.set_modified(modified);
+        }
+        output.set_times(times)?;
+        Ok(output.metadata()?.len())
+    })();
+
+    // 任何一步失败都只保留原文件
+    let result = written.and_then(|size| rooted::remove_file(root, path).map(|()| size));
+    if result.is_err() {
+        rooted::remove_file(root, &dest).ok();
+    }
+    result
+}
+
+fn encode(source: &mut File, output: File, format: CompressFormat) -> io::Result<File> {
+    let writer = BufWriter::new(output);
+    let writer = match format {
+        CompressFormat::Gz => {
+            let mut encoder = GzEncoder::new(writer, Compression::default());
+            io::copy(source, &mut encoder)?;
+            encoder.finish()?
+        }
+        CompressFormat::Zst => {
+            let mut encoder = zstd::Encoder::new(writer, 0)?;
+            io::copy(source, &mut encoder)?;
+            encoder.finish()?
+        }
+    };
+    writer.into_inner().map_err(|err| err.into_error())
+}
+
+// 解压刚写好的文件并与原文件逐块比较；压缩期间原文件被追加或改写时同样校验失败
+fn verify(source: &mut File, archive: File, format: CompressFormat) -> io::Result<()> {
+    source.seek(SeekFrom::Start(0))?;
+    let mut decoded: Box<dyn Read> = match format {
+        CompressFormat::Gz => Box::new(GzDecoder::new(BufReader::new(archive))),
+        CompressFormat::Zst => Box::new(zstd::Decoder::new(archive)?),
+    };
+
+    let mut expected = vec![0; CHUNK_SIZE];
+    let mut actual = vec![0; CHUNK_SIZE];
+    loop {
+        let expected_len = read_chunk(source, &mut expected)?;
+        let actual_len = read_chunk(&mut decoded, &mut actual)?;
+        if expected[..expected_len] != actual[..actual_len] {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "压缩文件校验失败, 内容与原文件不一致"));
+        }
+        if expected_len == 0 {
+            return Ok(());
+        }
+    }
+}
+
+// 读满缓冲区或读到文件末尾
+fn read_chunk(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    Ok(filled)
+}
+
+// 以 root 运行时压缩文件归原文件的所有者；普通用户无法更改时保留自己
+#[cfg(unix)]
+fn copy_owner(output: &File, metadata: &std::fs::Metadata) {
+    use std::os::unix::fs::{MetadataExt, fchown};
+    fchown(output, Some(metadata.uid()), Some(metadata.gid())).ok();
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_output: &File, _metadata: &std::fs::Metadata) {}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = OsString::from(path);
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+
+    // 跨越多个校验块、可以压缩的内容
+    fn content() -> Vec<u8> {
+        (0..200_000u32).flat_map(|index| format!("line {}\n", index % 977).into_bytes()).collect()
+    }
+
+    fn decode(path: &Path, format: CompressFormat) -> Vec<u8> {
+        let file = File::open(path).unwrap();
+        let mut decoded = Vec::new();
+        match format {
+            CompressFormat::Gz => GzDecoder::new(file).read_to_end(&mut decoded).unwrap(),
+            CompressFormat::Zst => zstd::Decoder::new(file).unwrap().read_to_end(&mut decoded).unwrap(),
+        };
+        decoded
+    }
+
+    #[test]
+    fn round_trip() {
+        for format in [CompressFormat::Gz, CompressFormat::Zst] {
+            let temp = tempfile::tempdir().unwrap();
+            let path = temp.path().join("app.log.1");
+            fs::write(&path, content()).unwrap();
+
+            let size = compress(temp.path(), &path, format).unwrap();
+            let dest = with_suffix(&path, format.extension());
+            assert!(!path.exists());
+            assert_eq!(fs::metadata(&dest).unwrap().len(), size);
+            assert!(size < content().len() as u64 / 10);
+            assert_eq!(decode(&dest, format), content());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_mode_owner_and_mtime() {
+        use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("app.log.1");
+        fs::write(&path, content()).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(10 * 24 * 3600);
+        File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+        // 以 root 运行时换成其他所有者，确认压缩文件跟随原文件
+        if unsafe { libc::geteuid() } == 0 {
+            std::os::unix::fs::chown(&path, Some(1234), Some(1234)).unwrap();
+        }
+        let original = fs::metadata(&path).unwrap();
+
+        compress(temp.path(), &path, CompressFormat::Gz).unwrap();
+        let archived = fs::metadata(with_suffix(&path, ".gz")).unwrap();
+        assert_eq!(archived.mode() & 0o7777, 0o640);
+        assert_eq!((archived.uid(), archived.gid()), (original.uid(), original.gid()));
+        assert_eq!(archived.modified().unwrap(), original.modified().unwrap());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_existing_archive() {
+        for format in [CompressFormat::Gz, CompressFormat::Zst] {
+            let temp = tempfile::tempdir().unwrap();
+            let path = temp.path().join("app.log.1");
+            let dest = with_suffix(&path, format.extension());
+            fs::write(&path, "new").unwrap();
+            fs::write(&dest, "existing archive").unwrap();
+
+            let error = compress(temp.path(), &path, format).unwrap_err();
+            assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+            assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+            assert_eq!(fs::read_to_string(&dest).unwrap(), "existing archive");
+        }
+    }
+
+    #[test]
+    fn verify_detects_mismatch() {
+        let temp = tempfile::tempdir().unwrap();
+        let source = temp.path().join("source");
+        fs::write(&source, content()).unwrap();
+        let archive = temp.path().join("archive.gz");
+        let mut different = content();
+        different[100_000] ^= 1;
+        let mut encoder = GzEncoder::new(File::create(&archive).unwrap(), Compression::default());
+        io::Write::write_all(&mut encoder, &different).unwrap();
+        encoder.finish().unwrap();
+
+        let error = verify(&mut File::open(&source).unwrap(), File::open(&archive).unwrap(), CompressFormat::Gz).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        // 原文件比压缩内容长（压缩期间被追加）时同样失败
+        let mut encoder = GzEncoder::new(File::create(&archive).unwrap(), Compression::default());
+        io::Write::write_all(&mut encoder, &content()[..1000]).unwrap();
+        encoder.finish().unwrap();
+        assert!(verify(&mut File::open(&source).unwrap(), File::open(&archive).unwrap(), CompressFormat::Gz).is_err());
+    }
+
+    // 命名管道读完后无法回到开头重新读取，校验一定失败：原文件保留，写了一半的压缩文件删除
+    #[cfg(unix)]
+    #[test]
+    fn failed_verification_keeps_original() {
+        let temp = tempfile::tempdir().unwrap();
+        let fifo = temp.path().join("app.log.1");
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+        let writer = {
+            let fifo = fifo.clone();
+            std::thread::spawn(move || fs::write(fifo, content()).unwrap())
+        };
+
+        assert!(compress(temp.path(), &fifo, CompressFormat::Gz).is_err());
+        writer.join().unwrap();
+        assert!(std::os::unix::fs::FileTypeExt::is_fifo(&fs::symlink_metadata(&fifo).unwrap().file_type()));
+        assert!(!with_suffix(&fifo, ".gz").exists());
+    }
+}
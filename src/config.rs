@@ -0,0 +1,606 @@
+use serde::Deserialize;
+
+use crate::{
+    container,
+    platform::{KnownFolder, Platform},
+    rules::RuleList,
+};
+
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Component, Path, PathBuf},
+};
+
+// 配置文件分两层：系统级配置先加载，用户级配置中出现的字段覆盖系统级配置
+const CONFIG_DIR_NAME: &str = "diskspace_free";
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub notification: NotificationConfig,
+    pub log_scan: LogScanConfig,
+    pub scan: ScanConfig,
+    pub dev_caches: DevCacheConfig,
+    pub package_caches: PackageCacheConfig,
+    pub containers: ContainerConfig,
+    pub sweep: SweepConfig,
+    pub xdg_cache: XdgCacheConfig,
+    pub journal: JournalConfig,
+    pub quarantine: QuarantineConfig,
+    pub trash: TrashConfig,
+    pub memory: MemoryConfig,
+    // 是否保留程序内置的清理目标
+    pub builtin_targets: bool,
+    pub targets: Vec<TargetConfig>,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    pub timeout_ms: u64,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogScanConfig {
+    pub enabled: bool,
+    pub drives: Vec<String>,     // 要扫描的驱动器
+    pub max_age_days: u64,       // 只清理超过该天数的日志文件
+    pub min_size_mb: u64,        // 只清理大于该大小的日志文件
+    pub keep_generations: usize, // 每个轮转组保留最新的几个轮转文件
+    pub compress: bool,          // 更旧的轮转文件和到期的日志原地压缩，而不是删除
+    pub compress_format: CompressFormat,
+    pub compressed_max_age_days: u64, // 压缩文件超过该天数后删除，0 表示永远保留
+    pub truncate_over_mb: u64,   // 仍被打开的当前日志超过该大小时截断，0 表示只按年龄和大小截断
+}
+
+// 日志原地压缩的格式
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressFormat {
+    #[default]
+    Gz,
+    Zst,
+}
+
+// 遍历目录时的挂载边界
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+    pub one_file_system: bool,           // 不进入其他文件系统和挂载点（包括绑定挂载）
+    pub excluded_fs_types: Vec<String>,  // 关闭 one_file_system 时仍然跳过的文件系统类型
+}
+
+// 开发工具缓存目标（cargo、npm、pip、go、gradle、maven），只在 Linux 等 Unix 系统上提供
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DevCacheConfig {
+    pub enabled: bool,
+    pub project_roots: Vec<PathBuf>, // 在这些目录下查找项目的锁文件，锁文件引用的依赖不清理
+    pub max_depth: usize,            // 查找锁文件的最大目录深度
+}
+
+// 包管理器缓存目标（apt、dnf、pacman、zypper），只在以管理员身份运行的 Linux 等 Unix 系统上提供
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct PackageCacheConfig {
+    pub enabled: bool,
+    pub keep_versions: usize, // 每个包除已安装的版本外，再保留最新下载的几个版本
+}
+
+// 容器引擎目标（Docker、Podman），通过引擎 Unix 套接字上的 API 清理，只在 Linux 等 Unix 系统上提供
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContainerConfig {
+    pub enabled: bool,
+    pub sockets: Vec<PathBuf>, // 引擎的 API 套接字，为空时按 DOCKER_HOST 和常见位置查找
+    pub min_age_days: u64,     // 只清理创建（构建缓存为最后使用）超过该天数的对象
+    pub volumes: bool,         // 是否清理没有被容器使用的数据卷；卷中可能有需要的数据，默认关闭
+}
+
+// 清空回收站：Linux 等 Unix 系统上清理 freedesktop.org 回收站（主目录和各挂载点上的），Windows 上始终清空整个回收站
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct TrashConfig {
+    pub min_age_days: u64, // 只清理移入回收站超过该天数的项，0 表示全部清理
+}
+
+// 释放内存：Windows 上整理所有进程的工作集；Linux 上的各项操作都默认关闭，需要单独开启，丢弃缓存和整理碎片需要 root
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    pub drop_caches: u8,                // 写入 /proc/sys/vm/drop_caches：0 不丢弃，1 页缓存，2 dentry 和 inode，3 两者
+    pub compact_memory: bool,           // 写入 /proc/sys/vm/compact_memory 整理内存碎片
+    pub pageout_processes: Vec<String>, // 按进程名（/proc/<pid>/comm）选择，用 process_madvise(MADV_PAGEOUT) 换出其内存
+    pub reclaim_cgroups: Vec<String>,   // cgroup v2 路径（相对于 /sys/fs/cgroup），写入 memory.reclaim 请求回收
+    pub reclaim_mb: u64,                // 每个 cgroup 请求回收的大小
+    pub top_processes: usize,           // 报告内存释放最多的几个进程（Windows 上为所有进程，Linux 上为 pageout_processes）
+}
+
+// $XDG_CACHE_HOME（默认 ~/.cache）目标，每个应用目录按策略清理，只在 Linux 等 Unix 系统上提供
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct XdgCacheConfig {
+    pub enabled: bool,
+    pub default_min_age_days: u64,          // 没有单独策略的应用目录：只清理超过该天数未变化的文件
+    pub apps: BTreeMap<String, AppPolicy>,  // 按应用目录名设置的策略，覆盖内置策略
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppPolicy {
+    pub keep: bool,         // 整个目录不清理
+    pub min_age_days: u64,  // 只清理超过该天数未变化的文件，0 表示全部清理
+}
+
+// systemd 日志目标：只删除已归档的日志文件，正在写入的文件永远保留
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct JournalConfig {
+    pub enabled: bool,
+    pub dir: PathBuf,
+    pub max_age_days: u64,  // 删除最后一条记录超过该天数的归档文件，0 表示不按时间删除
+    pub max_size_mb: u64,   // 每台机器的日志总量超过该大小时从最旧的归档文件开始删除，0 表示不限制
+}
+
+// sweep 子命令：清理长时间没有构建的 Rust 项目的 target/ 目录
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct SweepConfig {
+    pub project_roots: Vec<PathBuf>, // 在这些目录下查找 Cargo 项目
+    pub max_depth: usize,            // 查找项目的最大目录深度
+    pub max_idle_days: u64,          // 只清理超过该天数没有构建的项目
+    pub mode: SweepMode,
+}
+
+// target/ 中要清理的内容
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SweepMode {
+    #[default]
+    All,          // target/ 中的所有内容
+    Debug,        // 只清理 debug 构建产物
+    Incremental,  // 只清理增量编译缓存
+}
+
+// 隔离模式：先把要删除的项移到同一文件系统上的隔离目录，过期后再真正删除
+#[derive(Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuarantineConfig {
+    pub enabled: bool,
+    pub dir: PathBuf,      // 隔离区根目录，每次运行在其下建立一个子目录
+    pub expire_days: u64,  // 超过该天数的隔离记录会在下次清理时删除
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TargetConfig {
+    pub name: String,
+    pub path: Option<PathBuf>, // 省略时覆盖同名内置目标的年龄、大小和保留设置
+    #[serde(default)]
+    pub rule: RuleSet,
+    #[serde(default)]
+    pub rules: RuleList,    // 自定义规则，设置后代替 rule 选择的内置规则
+    pub min_age_days: Option<u64>,  // 只清理超过该天数未变化的项
+    pub age_by: Option<AgeBy>,
+    pub min_size_kb: Option<u64>,   // 只清理不小于该大小的项
+    pub keep_newest: Option<usize>, // 始终保留最新的 N 个文件（每个目录分别计算）
+    #[serde(default)]
+    pub keep: Vec<PathBuf>, // 相对于 path 的目录，这些目录本身不删除
+}
+
+// 计算年龄使用的时间戳；Windows 上 ctime 取创建时间
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AgeBy {
+    #[default]
+    Mtime,
+    Atime,
+    Ctime,
+}
+
+// 一条自定义规则：exclude 对整个规则列表生效，min_age_days / min_size_kb 只约束本条的 include
+#[derive(Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct RuleConfig {
+    pub name: Option<String>,
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub min_age_days: u64,
+    #[serde(default)]
+    pub min_size_kb: u64,
+}
+
+// 目标目录内使用的内置清理规则
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuleSet {
+    #[default]
+    Auto,       // 按目录名自动选择规则
+    All,        // 清理目录下的所有内容
+    Downloads,  // 只清理下载产生的临时文件
+    Firefox,    // 只清理名称中带 cache/temp 的项
+    Recent,     // 只清理 .lnk 快捷方式
+    TempFiles,  // 只清理临时文件扩展名
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            notification: NotificationConfig::default(),
+            log_scan: LogScanConfig::default(),
+            scan: ScanConfig::default(),
+            dev_caches: DevCacheConfig::default(),
+            package_caches: PackageCacheConfig::default(),
+            containers: ContainerConfig::default(),
+            sweep: SweepConfig::default(),
+            xdg_cache: XdgCacheConfig::default(),
+            journal: JournalConfig::default(),
+            quarantine: QuarantineConfig::default(),
+            trash: TrashConfig::default(),
+            memory: MemoryConfig::default(),
+            builtin_targets: true,
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self { enabled: true, timeout_ms: 5000 }
+    }
+}
+
+impl Default for LogScanConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            drives: vec!["C:".to_string(), "D:".to_string(), "E:".to_string()],
+            max_age_days: 30,
+            min_size_mb: 1,
+            keep_generations: 3,
+            compress: false,
+            compress_format: CompressFormat::Gz,
+            compressed_max_age_days: 180,
+            truncate_over_mb: 0,
+        }
+    }
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        let excluded = [
+            "nfs", "nfs4", "cifs", "smb3", "smbfs", "9p", "fuse", "fuseblk", "overlay", "autofs",
+            "proc", "sysfs", "devtmpfs", "devpts", "cgroup", "cgroup2", "debugfs", "tracefs",
+            "securityfs", "pstore", "bpf", "mqueue", "hugetlbfs", "binfmt_misc", "configfs",
+        ];
+        Self { one_file_system: true, excluded_fs_types: excluded.iter().map(|fs_type| fs_type.to_string()).collect() }
+    }
+}
+
+impl Default for DevCacheConfig {
+    fn default() -> Self {
+        Self { enabled: true, project_roots: Vec::new(), max_depth: 6 }
+    }
+}
+
+impl Default for PackageCacheConfig {
+    fn default() -> Self {
+        Self { enabled: true, keep_versions: 1 }
+    }
+}
+
+impl Default for ContainerConfig {
+    fn default() -> Self {
+        Self { enabled: true, sockets: Vec::new(), min_age_days: 7, volumes: false }
+    }
+}
+
+impl Default for TrashConfig {
+    fn default() -> Self {
+        Self { min_age_days: 30 }
+    }
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self { drop_caches: 0, compact_memory: false, pageout_processes: Vec::new(), reclaim_cgroups: Vec::new(), reclaim_mb: 256, top_processes: 10 }
+    }
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        Self { project_roots: Vec::new(), max_depth: 6, max_idle_days: 30, mode: SweepMode::All }
+    }
+}
+
+impl Default for XdgCacheConfig {
+    fn default() -> Self {
+        Self { enabled: true, default_min_age_days: 30, apps: BTreeMap::new() }
+    }
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self { enabled: true, dir: PathBuf::from("/var/log/journal"), max_age_days: 30, max_size_mb: 1024 }
+    }
+}
+
+impl Default for QuarantineConfig {
+    fn default() -> Self {
+        // 项目目录和隔离区的默认位置由 Config::load 按所在系统填入，见 platform_defaults
+        Self {
+            enabled: false,
+            dir: env::temp_dir().join(CONFIG_DIR_NAME).join("quarantine"),
+            expire_days: 7,
+        }
+    }
+}
+
+pub struct ConfigError {
+    path: PathBuf,
+    message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "配置文件 {} 无效: {}", self.path.display(), self.message.trim_end())
+    }
+}
+
+impl Config {
+    // 加载系统级和用户级配置；指定 explicit 时用它代替用户级配置
+    pub fn load(explicit: Option<&Path>, platform: &dyn Platform) -> Result<Self, ConfigError> {
+        let home = platform.known_folder(KnownFolder::Home);
+        let mut layers = Vec::new();
+        if let Some(dir) = platform.known_folder(KnownFolder::SystemConfig) {
+            layers.push((dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME), false));
+        }
+        match explicit {
+            Some(path) => layers.push((path.to_path_buf(), true)),
+            None => {
+                if let Some(dir) = platform.known_folder(KnownFolder::Config) {
+                    layers.push((dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME), false));
+                }
+            }
+        }
+
+        let mut merged = platform_defaults(platform);
+        let mut last_path = PathBuf::new();
+        for (path, required) in layers {
+            let Some(table) = read_layer(&path, required, home.as_deref())? else {
+                continue;
+            };
+            merge_tables(&mut merged, table);
+            last_path = path;
+        }
+
+        let config: Config = toml::Value::Table(merged)
+            .try_into()
+            .map_err(|err: toml::de::Error| ConfigError { path: last_path.clone(), message: err.to_string() })?;
+        config.validate(home.as_deref()).map_err(|message| ConfigError { path: last_path, message })?;
+        Ok(config)
+    }
+
+    fn validate(&self, home: Option<&Path>) -> Result<(), String> {
+        if self.notification.timeout_ms == 0 {
+            return Err("notification.timeout_ms 必须大于 0".to_string());
+        }
+        if self.log_scan.drives.iter().any(|drive| drive.trim().is_empty()) {
+            return Err("log_scan.drives 中不能有空字符串".to_string());
+        }
+        if let Some(root) = self.dev_caches.project_roots.iter().find(|root| !root.is_absolute()) {
+            return Err(format!("dev_caches.project_roots 必须是绝对路径: {}", root.display()));
+        }
+        if let Some(root) = self.sweep.project_roots.iter().find(|root| !root.is_absolute()) {
+            return Err(format!("sweep.project_roots 必须是绝对路径: {}", root.display()));
+        }
+        if let Some(app) = self.xdg_cache.apps.keys().find(|app| app.is_empty() || app.contains(['/', '\\'])) {
+            return Err(format!("xdg_cache.apps 中的名称必须是单个目录名: {:?}", app));
+        }
+        if let Some(socket) = self.containers.sockets.iter().find(|socket| !socket.is_absolute()) {
+            return Err(format!("containers.sockets 必须是绝对路径: {}", socket.display()));
+        }
+        if self.memory.drop_caches > 3 {
+            return Err(format!("memory.drop_caches 必须是 0 到 3: {}", self.memory.drop_caches));
+        }
+        if let Some(cgroup) = self.memory.reclaim_cgroups.iter().find(|cgroup| {
+            !Path::new(cgroup.trim_start_matches('/')).components().all(|component| matches!(component, Component::Normal(_)))
+        }) {
+            return Err(format!("memory.reclaim_cgroups 必须是 /sys/fs/cgroup 下的路径: {:?}", cgroup));
+        }
+        if self.memory.reclaim_mb == 0 {
+            return Err("memory.reclaim_mb 必须大于 0".to_string());
+        }
+        if !self.journal.dir.is_absolute() {
+            return Err(format!("journal.dir 必须是绝对路径: {}", self.journal.dir.display()));
+        }
+        if !self.quarantine.dir.is_absolute() {
+            return Err(format!("quarantine.dir 必须是绝对路径: {}", self.quarantine.dir.display()));
+        }
+
+        for (index, target) in self.targets.iter().enumerate() {
+            if target.name.trim().is_empty() {
+                return Err(format!("targets[{}].name 不能为空", index));
+            }
+            if let Some(path) = target.path.as_ref().filter(|path| !path.is_absolute()) {
+                return Err(format!("targets[{}] ({}) 的 path 必须是绝对路径: {}",
+                    index, target.name, path.display()));
+            }
+            if target.path.is_none() && (target.rule != RuleSet::Auto || !target.rules.is_empty()) {
+                return Err(format!("targets[{}] ({}) 没有 path, 只能覆盖内置目标的年龄、大小和保留设置, 不能设置 rule 或 rules",
+                    index, target.name));
+            }
+            if target.rule != RuleSet::Auto && !target.rules.is_empty() {
+                return Err(format!("targets[{}] ({}) 不能同时设置 rule 和 rules", index, target.name));
+            }
+            if let Some(keep) = target.keep.iter().find(|keep| !keep.is_relative()) {
+                return Err(format!("targets[{}] ({}) 的 keep 必须是相对路径: {}",
+                    index, target.name, keep.display()));
+            }
+            // 直接删除容器引擎存储中的文件会损坏引擎，只能通过容器引擎目标清理
+            if let Some(path) = &target.path
+                && let Some(storage) = container::storage_dirs(home).into_iter().find(|storage| path.starts_with(storage))
+            {
+                return Err(format!("targets[{}] ({}) 的 path 位于容器引擎的存储目录 {} 中",
+                    index, target.name, storage.display()));
+            }
+            if self.targets[..index].iter().any(|other| other.name == target.name) {
+                return Err(format!("targets[{}] 的名称 {} 重复", index, target.name));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 依赖所在系统的默认值：项目目录默认为用户主目录，隔离区位于程序数据目录中。
+// 作为最底层合并，配置文件中的设置覆盖它们；路径无法表示为 UTF-8 时保留 Default 中的值
+fn platform_defaults(platform: &dyn Platform) -> toml::Table {
+    let mut defaults = toml::Table::new();
+    if let Some(home) = platform.known_folder(KnownFolder::Home).and_then(|home| home.to_str().map(str::to_string)) {
+        for section in ["dev_caches", "sweep"] {
+            let mut table = toml::Table::new();
+            table.insert("project_roots".to_string(), toml::Value::Array(vec![toml::Value::String(home.clone())]));
+            defaults.insert(section.to_string(), toml::Value::Table(table));
+        }
+    }
+    if let Some(dir) = platform.known_folder(KnownFolder::Data)
+        .map(|data| data.join(CONFIG_DIR_NAME).join("quarantine"))
+        .and_then(|dir| dir.to_str().map(str::to_string))
+    {
+        let mut table = toml::Table::new();
+        table.insert("dir".to_string(), toml::Value::String(dir));
+        defaults.insert("quarantine".to_string(), toml::Value::Table(table));
+    }
+    defaults
+}
+
+// 读取并单独校验一层配置，这样语法错误能指向具体的文件
+fn read_layer(path: &Path, required: bool, home: Option<&Path>) -> Result<Option<toml::Table>, ConfigError> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if !required && err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(ConfigError { path: path.to_path_buf(), message: err.to_string() }),
+    };
+
+    // 直接按 Config 解析一次，类型错误会带上行号
+    toml::from_str::<Config>(&content)
+        .map_err(|err| ConfigError { path: path.to_path_buf(), message: err.to_string() })?;
+
+    let mut table: toml::Table = toml::from_str(&content)
+        .map_err(|err: toml::de::Error| ConfigError { path: path.to_path_buf(), message: err.to_string() })?;
+    expand_paths(&mut table, home);
+
+    let layer: Config = toml::Value::Table(table.clone())
+        .try_into()
+        .map_err(|err: toml::de::Error| ConfigError { path: path.to_path_buf(), message: err.to_string() })?;
+    layer.validate(home).map_err(|message| ConfigError { path: path.to_path_buf(), message })?;
+
+    Ok(Some(table))
+}
+
+// 子表逐字段合并，其余值（包括数组）整体覆盖
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base_table)), toml::Value::Table(overlay_table)) => {
+                merge_tables(base_table, overlay_table);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// 配置中的路径支持以 ~ 开头表示用户主目录
+fn expand_paths(table: &mut toml::Table, home: Option<&Path>) {
+    if let Some(toml::Value::Array(targets)) = table.get_mut("targets") {
+        for target in targets {
+            if let Some(toml::Value::String(path)) = target.get_mut("path") {
+                expand_home(path, home);
+            }
+        }
+    }
+
+    for section in ["dev_caches", "sweep"] {
+        if let Some(toml::Value::Array(roots)) = table.get_mut(section).and_then(|s| s.get_mut("project_roots")) {
+            for root in roots {
+                if let toml::Value::String(path) = root {
+                    expand_home(path, home);
+                }
+            }
+        }
+    }
+
+    if let Some(toml::Value::String(path)) = table.get_mut("quarantine").and_then(|q| q.get_mut("dir")) {
+        expand_home(path, home);
+    }
+}
+
+fn expand_home(path: &mut String, home: Option<&Path>) {
+    if let Some(rest) = path.strip_prefix("~").filter(|rest| rest.is_empty() || rest.starts_with(['/', '\\']))
+        && let Some(home) = home
+    {
+        *path = format!("{}{}", home.display(), rest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::Fake;
+
+    // 各用户目录都指向临时目录的 Fake
+    fn platform(root: &Path) -> Fake {
+        let folders = [
+            (KnownFolder::Home, "home"),
+            (KnownFolder::Data, "data"),
+            (KnownFolder::Config, "config"),
+            (KnownFolder::SystemConfig, "etc"),
+        ];
+        Fake { folders: folders.iter().map(|&(folder, dir)| (folder, root.join(dir))).collect(), ..Fake::default() }
+    }
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn load_uses_platform_folders() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(&root.join("etc/diskspace_free/config.toml"), "[dev_caches]\nmax_depth = 3\n");
+        write(&root.join("config/diskspace_free/config.toml"), "[sweep]\nproject_roots = [\"~/src\"]\n");
+
+        let config = Config::load(None, &platform(root)).map_err(|err| err.to_string()).unwrap();
+        assert_eq!(config.dev_caches.project_roots, [root.join("home")]);
+        assert_eq!(config.dev_caches.max_depth, 3);
+        assert_eq!(config.sweep.project_roots, [root.join("home/src")]);
+        assert_eq!(config.quarantine.dir, root.join("data/diskspace_free/quarantine"));
+    }
+
+    #[test]
+    fn explicit_config_replaces_user_layer() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(&root.join("config/diskspace_free/config.toml"), "[sweep]\nmax_depth = 2\n");
+        let explicit = root.join("explicit.toml");
+        write(&explicit, "[quarantine]\ndir = \"~/quarantine\"\n");
+
+        let config = Config::load(Some(&explicit), &platform(root)).map_err(|err| err.to_string()).unwrap();
+        assert_eq!(config.sweep.max_depth, 6);
+        assert_eq!(config.quarantine.dir, root.join("home/quarantine"));
+
+        // 主目录下的容器存储按 Fake 的主目录判断
+        write(&explicit, "[[targets]]\nname = \"podman\"\npath = \"~/.local/share/containers/storage\"\n");
+        assert!(Config::load(Some(&explicit), &platform(root)).is_err());
+    }
+}
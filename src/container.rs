@@ -0,0 +1,496 @@
+use serde::Deserialize;
+
+use std::{
+    env, io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Action, PlanEntry, Retention, Target, config::ContainerConfig, is_old_enough};
+
+// 容器引擎（Docker、Podman）的存储由引擎自己管理，直接删除 /var/lib/docker 等目录会损坏引擎。
+// 这里通过引擎 Unix 套接字上的 Docker 兼容 API 列出可回收的对象：已停止的容器、悬空镜像、构建缓存，
+// 以及开启 volumes 时没有被使用的数据卷，按年龄逐个删除。计划中的路径为 <套接字>/<类型>/<ID>
+
+const CONTAINERS: &str = "containers";
+const IMAGES: &str = "images";
+const VOLUMES: &str = "volumes";
+const BUILD_CACHE: &str = "build-cache";
+
+// /system/df 需要计算各对象的大小，对象很多时较慢
+const TIMEOUT: Duration = Duration::from_secs(120);
+
+// 返回各引擎的目标名称和套接字；配置中没有指定套接字时按 DOCKER_HOST 和常见位置查找
+pub fn targets(config: &ContainerConfig) -> Vec<(&'static str, PathBuf)> {
+    let sockets = if config.sockets.is_empty() { default_sockets() } else { config.sockets.clone() };
+
+    let mut targets: Vec<(&'static str, PathBuf)> = Vec::new();
+    let mut seen = Vec::new();
+    for socket in sockets {
+        // /var/run 通常是指向 /run 的链接，同一个套接字只算一次
+        let Ok(canonical) = socket.canonicalize() else {
+            continue;
+        };
+        if seen.contains(&canonical) {
+            continue;
+        }
+        seen.push(canonical);
+        let name = if socket.to_string_lossy().contains("podman") { "podman" } else { "docker" };
+        targets.push((name, socket));
+    }
+    targets
+}
+
+fn default_sockets() -> Vec<PathBuf> {
+    let mut sockets = Vec::new();
+    if let Some(host) = env::var("DOCKER_HOST").ok().and_then(|host| host.strip_prefix("unix://").map(PathBuf::from)) {
+        sockets.push(host);
+    }
+    sockets.push(PathBuf::from("/var/run/docker.sock"));
+    sockets.push(PathBuf::from("/run/podman/podman.sock"));
+    if let Some(runtime_dir) = env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from).filter(|dir| dir.is_absolute()) {
+        sockets.push(runtime_dir.join("docker.sock"));
+        sockets.push(runtime_dir.join("podman").join("podman.sock"));
+    }
+    sockets
+}
+
+// 引擎的存储目录，配置的清理目标不能位于其中
+pub fn storage_dirs(home: Option<&Path>) -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("/var/lib/docker"), PathBuf::from("/var/lib/containers")];
+    dirs.extend(home.map(|home| home.join(".local").join("share").join("containers")));
+    dirs
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct SystemDf {
+    images: Option<Vec<Image>>,
+    containers: Option<Vec<Container>>,
+    volumes: Option<Vec<Volume>>,
+    build_cache: Option<Vec<BuildCache>>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct Image {
+    id: String,
+    created: i64,
+    size: i64,
+    shared_size: i64,
+    repo_tags: Option<Vec<String>>,
+    containers: i64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct Container {
+    id: String,
+    created: i64,
+    state: String,
+    size_rw: i64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct Volume {
+    name: String,
+    created_at: Option<String>,
+    usage_data: Option<UsageData>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct UsageData {
+    size: i64,
+    ref_count: i64,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase", default)]
+struct BuildCache {
+    #[serde(rename = "ID")]
+    id: String,
+    size: i64,
+    created_at: Option<String>,
+    last_used_at: Option<String>,
+    in_use: bool,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    message: String,
+}
+
+// 引擎没有运行或无权访问套接字时不生成计划
+pub fn plan(target: &Target, config: &ContainerConfig) -> Vec<PlanEntry> {
+    let Ok(df) = get_json::<SystemDf>(&target.path, "/system/df") else {
+        return Vec::new();
+    };
+    let retention = Retention { min_age_days: config.min_age_days, ..Retention::default() };
+    let mut plan = Vec::new();
+    let mut push = |kind: &str, id: &str, size: i64, time: Option<SystemTime>, category: &'static str| {
+        if id.is_empty() || !is_old_enough(time, &retention) {
+            return;
+        }
+        plan.push(PlanEntry {
+            target: target.name.clone(),
+            root: target.path.clone(),
+            path: target.path.join(kind).join(id),
+            size: size.max(0) as u64,
+            is_dir: false,
+            rule: format!("{}-{}", target.name, kind),
+            category,
+            time,
+            action: Action::Prune,
+        });
+    };
+
+    for container in df.containers.unwrap_or_default() {
+        if matches!(container.state.as_str(), "exited" | "created" | "dead") {
+            push(CONTAINERS, &container.id, container.size_rw, unix_time(container.created), "已停止的容器");
+        }
+    }
+    // 没有标签、也没有容器使用的悬空镜像；与其他镜像共享的层不会被释放
+    for image in df.images.unwrap_or_default() {
+        let dangling = image.repo_tags.as_ref().is_none_or(|tags| tags.iter().all(|tag| tag == "<none>:<none>"));
+        if dangling && image.containers <= 0 {
+            push(IMAGES, &image.id, image.size - image.shared_size.max(0), unix_time(image.created), "悬空镜像");
+        }
+    }
+    for cache in df.build_cache.unwrap_or_default() {
+        if !cache.in_use {
+            let time = cache.last_used_at.or(cache.created_at).as_deref().and_then(parse_rfc3339);
+            push(BUILD_CACHE, &cache.id, cache.size, time, "构建缓存");
+        }
+    }
+    if config.volumes {
+        for volume in df.volumes.unwrap_or_default() {
+            let Some(usage) = volume.usage_data.filter(|usage| usage.ref_count == 0) else {
+                continue;
+            };
+            push(VOLUMES, &volume.name, usage.size, volume.created_at.as_deref().and_then(parse_rfc3339), "未使用的数据卷");
+        }
+    }
+    plan
+}
+
+// 删除计划中的一个对象；镜像和数据卷不强制删除，扫描后被重新使用时由引擎拒绝
+pub fn remove(socket: &Path, path: &Path) -> io::Result<()> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, format!("不是容器引擎对象: {}", path.display()));
+    let relative = path.strip_prefix(socket).map_err(|_| invalid())?;
+    let (Some(kind), Some(id)) = (relative.parent().and_then(Path::to_str), relative.file_name().and_then(|id| id.to_str())) else {
+        return Err(invalid());
+    };
+
+    match kind {
+        CONTAINERS | IMAGES | VOLUMES => request(socket, "DELETE", &format!("/{}/{}", kind, percent_encode(id))).map(drop),
+        BUILD_CACHE => {
+            let filters = percent_encode(&serde_json::json!({ "id": [id] }).to_string());
+            request(socket, "POST", &format!("/build/prune?filters={}", filters)).map(drop)
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn get_json<T: for<'de> Deserialize<'de>>(socket: &Path, path: &str) -> io::Result<T> {
+    let body = request(socket, "GET", path)?;
+    serde_json::from_slice(&body).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+// 发送一个没有请求体的 HTTP/1.1 请求，返回响应体；非 2xx 响应转换为错误，错误信息取引擎返回的 message
+#[cfg(unix)]
+fn request(socket: &Path, method: &str, path: &str) -> io::Result<Vec<u8>> {
+    use std::{
+        io::{Read, Write},
+        os::unix::net::UnixStream,
+    };
+
+    let mut stream = UnixStream::connect(socket)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    write!(stream, "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: 0\r\n\r\n", method, path)?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("无效的 HTTP 响应: {}", message));
+    let header_end = response.windows(4).position(|window| window == b"\r\n\r\n").ok_or_else(|| invalid("没有响应头"))?;
+    let head = String::from_utf8_lossy(&response[..header_end]);
+    let mut lines = head.lines();
+    let status: u16 = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|status| status.parse().ok())
+        .ok_or_else(|| invalid("没有状态码"))?;
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("transfer-encoding") && value.trim().eq_ignore_ascii_case("chunked")
+        })
+    });
+
+    let body = &response[header_end + 4..];
+    let body = if chunked { dechunk(body).ok_or_else(|| invalid("分块编码错误"))? } else { body.to_vec() };
+    if !(200..300).contains(&status) {
+        let message = serde_json::from_slice::<ErrorResponse>(&body)
+            .map(|error| error.message)
+            .unwrap_or_else(|_| String::from_utf8_lossy(&body).trim().to_string());
+        let kind = if status == 404 { io::ErrorKind::NotFound } else { io::ErrorKind::Other };
+        return Err(io::Error::new(kind, format!("引擎返回 {}: {}", status, message)));
+    }
+    Ok(body)
+}
+
+#[cfg(not(unix))]
+fn request(_socket: &Path, _method: &str, _path: &str) -> io::Result<Vec<u8>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "只支持 Unix 套接字"))
+}
+
+// Transfer-Encoding: chunked 的响应体：每块为 "十六进制长度\r\n数据\r\n"，长度为 0 的块结束
+#[cfg(unix)]
+fn dechunk(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        let line_end = body.windows(2).position(|window| window == b"\r\n")?;
+        let size_field = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size = usize::from_str_radix(size_field.split(';').next()?.trim(), 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
+
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn unix_time(secs: i64) -> Option<SystemTime> {
+    (secs > 0).then(|| UNIX_EPOCH + Duration::from_secs(secs as u64))
+}
+
+// 引擎返回的 RFC 3339 时间，如 "2024-05-01T12:34:56.123456789Z" 或带 "+08:00" 时区；
+// 未设置的时间为 "0001-01-01T00:00:00Z"，早于 Unix 纪元，视为未知
+fn parse_rfc3339(text: &str) -> Option<SystemTime> {
+    let (date, time) = text.split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i64>().ok());
+    let (year, month, day) = (date.next()??, date.next()??, date.next()??);
+
+    let (clock, offset) = match time.strip_suffix('Z') {
+        Some(clock) => (clock, 0),
+        None => {
+            let split = time.rfind(['+', '-'])?;
+            let (hours, minutes) = time[split + 1..].split_once(':')?;
+            let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+            (&time[..split], if time[split..].starts_with('-') { -offset } else { offset })
+        }
+    };
+    let mut clock = clock.split('.').next()?.splitn(3, ':').map(|part| part.parse::<i64>().ok());
+    let (hour, minute, second) = (clock.next()??, clock.next()??, clock.next()??);
+
+    let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second - offset;
+    unix_time(secs)
+}
+
+// 公历日期到 1970-01-01 的天数
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+// 模拟引擎的 API 需要 Unix 套接字
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    const DAY: i64 = 24 * 3600;
+
+    fn now() -> i64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+    }
+
+    // quarantine 中的 UTC 时间格式为 YYYYMMDD-HHMMSS
+    fn rfc3339(secs: i64) -> String {
+        let time = crate::quarantine::format_run_time(secs as u64);
+        format!("{}-{}-{}T{}:{}:{}.123456789Z", &time[..4], &time[4..6], &time[6..8], &time[9..11], &time[11..13], &time[13..])
+    }
+
+    fn system_df() -> String {
+        let (old, new) = (now() - 30 * DAY, now());
+        serde_json::json!({
+            "Containers": [
+                { "Id": "exited-old", "Created": old, "State": "exited", "SizeRw": 100 },
+                { "Id": "exited-new", "Created": new, "State": "exited", "SizeRw": 100 },
+                { "Id": "running", "Created": old, "State": "running", "SizeRw": 100 },
+            ],
+            "Images": [
+                { "Id": "sha256:dangling", "Created": old, "Size": 1000, "SharedSize": 400, "RepoTags": ["<none>:<none>"], "Containers": 0 },
+                { "Id": "sha256:untagged", "Created": old, "Size": 500, "SharedSize": -1, "RepoTags": null, "Containers": 0 },
+                { "Id": "sha256:tagged", "Created": old, "Size": 1000, "RepoTags": ["app:latest"], "Containers": 0 },
+                { "Id": "sha256:used", "Created": old, "Size": 1000, "RepoTags": ["<none>:<none>"], "Containers": 1 },
+            ],
+            "BuildCache": [
+                { "ID": "cache-old", "Size": 300, "CreatedAt": rfc3339(old), "LastUsedAt": rfc3339(old), "InUse": false },
+                { "ID": "cache-recent", "Size": 300, "CreatedAt": rfc3339(old), "LastUsedAt": rfc3339(new), "InUse": false },
+                { "ID": "cache-in-use", "Size": 300, "CreatedAt": rfc3339(old), "InUse": true },
+            ],
+            "Volumes": [
+                { "Name": "unused", "CreatedAt": rfc3339(old), "UsageData": { "Size": 50, "RefCount": 0 } },
+                { "Name": "mounted", "CreatedAt": rfc3339(old), "UsageData": { "Size": 50, "RefCount": 1 } },
+            ],
+        })
+        .to_string()
+    }
+
+    fn response(status: &str, body: &str) -> String {
+        format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}", status, body.len(), body)
+    }
+
+    fn chunked_response(status: &str, body: &str) -> String {
+        let mut response = format!("HTTP/1.1 {}\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n", status);
+        for chunk in body.as_bytes().chunks(64) {
+            response.push_str(&format!("{:x}\r\n{}\r\n", chunk.len(), std::str::from_utf8(chunk).unwrap()));
+        }
+        response.push_str("0\r\n\r\n");
+        response
+    }
+
+    // 在临时目录的 Unix 套接字上按顺序返回给定的响应，每个连接一个；线程结束时返回收到的请求行
+    fn serve(responses: Vec<String>) -> (tempfile::TempDir, PathBuf, std::thread::JoinHandle<Vec<String>>) {
+        use std::{
+            io::{BufRead, BufReader, Write},
+            os::unix::net::UnixListener,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let socket = dir.path().join("docker.sock");
+        let listener = UnixListener::bind(&socket).unwrap();
+        let server = std::thread::spawn(move || {
+            responses
+                .into_iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).unwrap();
+                    let mut header = String::new();
+                    while reader.read_line(&mut header).unwrap() > 0 && header != "\r\n" {
+                        header.clear();
+                    }
+                    stream.write_all(response.as_bytes()).unwrap();
+                    request_line.trim_end().to_string()
+                })
+                .collect()
+        });
+        (dir, socket, server)
+    }
+
+    fn planned(socket: &Path, config: &ContainerConfig) -> Vec<(String, u64)> {
+        let target = Target::new("docker", socket).with_container(config.clone());
+        let mut plan: Vec<(String, u64)> = plan(&target, config)
+            .into_iter()
+            .map(|entry| (entry.path.strip_prefix(socket).unwrap().to_string_lossy().into_owned(), entry.size))
+            .collect();
+        plan.sort();
+        plan
+    }
+
+    #[test]
+    fn plan_filters_by_age_and_dangling() {
+        let body = system_df();
+        let (_dir, socket, server) = serve(vec![response("200 OK", &body), chunked_response("200 OK", &body)]);
+        let expected = [
+            ("build-cache/cache-old".to_string(), 300),
+            ("containers/exited-old".to_string(), 100),
+            ("images/sha256:dangling".to_string(), 600),
+            ("images/sha256:untagged".to_string(), 500),
+        ];
+
+        let config = ContainerConfig::default();
+        assert_eq!(planned(&socket, &config), expected);
+        // 分块编码的响应得到相同的结果；开启 volumes 时加上没有被使用的数据卷
+        let config = ContainerConfig { volumes: true, ..config };
+        let mut with_volumes = expected.to_vec();
+        with_volumes.push(("volumes/unused".to_string(), 50));
+        assert_eq!(planned(&socket, &config), with_volumes);
+        assert_eq!(server.join().unwrap(), ["GET /system/df HTTP/1.1", "GET /system/df HTTP/1.1"]);
+    }
+
+    #[test]
+    fn plan_is_empty_when_engine_fails() {
+        let (_dir, socket, server) = serve(vec![response("500 Internal Server Error", "{\"message\":\"down\"}")]);
+        assert!(planned(&socket, &ContainerConfig::default()).is_empty());
+        server.join().unwrap();
+        // 套接字不存在
+        assert!(planned(Path::new("/nonexistent/docker.sock"), &ContainerConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn remove_sends_engine_requests() {
+        let (_dir, socket, server) = serve(vec![
+            response("204 No Content", ""),
+            response("200 OK", "{\"CachesDeleted\":[\"abc\"],\"SpaceReclaimed\":300}"),
+            chunked_response("404 Not Found", "{\"message\":\"No such image: sha256:gone\"}"),
+            response("409 Conflict", "{\"message\":\"volume is in use\"}"),
+        ]);
+
+        remove(&socket, &socket.join(CONTAINERS).join("exited-old")).unwrap();
+        remove(&socket, &socket.join(BUILD_CACHE).join("abc")).unwrap();
+        let not_found = remove(&socket, &socket.join(IMAGES).join("sha256:gone")).unwrap_err();
+        assert_eq!(not_found.kind(), io::ErrorKind::NotFound);
+        assert!(not_found.to_string().contains("No such image"));
+        let conflict = remove(&socket, &socket.join(VOLUMES).join("data")).unwrap_err();
+        assert_eq!(conflict.kind(), io::ErrorKind::Other);
+        assert!(conflict.to_string().contains("409") && conflict.to_string().contains("volume is in use"));
+
+        assert_eq!(server.join().unwrap(), [
+            "DELETE /containers/exited-old HTTP/1.1",
+            "POST /build/prune?filters=%7B%22id%22%3A%5B%22abc%22%5D%7D HTTP/1.1",
+            "DELETE /images/sha256%3Agone HTTP/1.1",
+            "DELETE /volumes/data HTTP/1.1",
+        ]);
+    }
+
+    #[test]
+    fn remove_rejects_other_paths() {
+        let socket = Path::new("/run/docker.sock");
+        for path in ["/run/other.sock/containers/a", "/run/docker.sock/networks/a", "/run/docker.sock/a"] {
+            assert_eq!(remove(socket, Path::new(path)).unwrap_err().kind(), io::ErrorKind::InvalidInput, "{}", path);
+        }
+    }
+
+    #[test]
+    fn dechunk_bodies() {
+        assert_eq!(dechunk(b"5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\n").unwrap(), b"hello world");
+        assert_eq!(dechunk(b"A\r\n0123456789\r\n0\r\n\r\n").unwrap(), b"0123456789");
+        assert_eq!(dechunk(b"0\r\n\r\n").unwrap(), b"");
+        // 截断或长度不是十六进制
+        assert!(dechunk(b"5\r\nhel").is_none());
+        assert!(dechunk(b"5\r\nhello\r\n").is_none());
+        assert!(dechunk(b"zz\r\nhello\r\n0\r\n\r\n").is_none());
+    }
+
+    #[test]
+    fn parse_rfc3339_times() {
+        let at = |secs: u64| Some(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:10Z"), at(10));
+        assert_eq!(parse_rfc3339("2024-05-01T12:34:56.123456789Z"), at(1714566896));
+        assert_eq!(parse_rfc3339("2024-05-01T20:34:56+08:00"), at(1714566896));
+        assert_eq!(parse_rfc3339("2024-05-01T07:34:56.5-05:00"), at(1714566896));
+        assert_eq!(parse_rfc3339("2000-02-29T00:00:00Z"), at(951782400));
+        // 未设置的时间和无法解析的文本
+        assert_eq!(parse_rfc3339("0001-01-01T00:00:00Z"), None);
+        assert_eq!(parse_rfc3339("2024-05-01"), None);
+        assert_eq!(parse_rfc3339("2024-05-01T12:34Z"), None);
+    }
+}
@@ -0,0 +1,432 @@
+use serde::Deserialize;
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use crate::{
+    config::{DevCacheConfig, RuleConfig},
+    rules::RuleList,
+};
+
+// 开发工具的下载缓存。每个工具一个目标，规则在扫描时才生成：
+// 先在 project_roots 下查找各项目的锁文件，锁文件仍然引用的包不会被清理
+#[derive(Clone, Copy)]
+enum Tool {
+    Cargo,
+    Npm,
+    Pip,
+    Go,
+    Gradle,
+    Maven,
+}
+
+#[derive(Clone)]
+pub struct DevCache {
+    tool: Tool,
+    projects: Arc<Projects>,
+}
+
+// 所有开发缓存目标共用一次锁文件查找
+struct Projects {
+    roots: Vec<PathBuf>,
+    max_depth: usize,
+    skip: Vec<PathBuf>, // 缓存目录本身，其中的依赖包自带的锁文件不算
+    lockfiles: OnceLock<Vec<PathBuf>>,
+}
+
+const LOCKFILE_NAMES: &[&str] = &["Cargo.lock", "package-lock.json", "go.sum", "gradle.lockfile"];
+// 这些目录中不会有需要考虑的项目
+const SKIPPED_DIR_NAMES: &[&str] = &["node_modules", "target", "build"];
+
+// 返回各工具的目标名称、缓存目录和规则生成器
+pub fn targets(config: &DevCacheConfig, home: Option<&Path>) -> Vec<(&'static str, PathBuf, DevCache)> {
+    let caches: Vec<(&'static str, Tool, PathBuf)> = [
+        ("cargo", Tool::Cargo, cargo_home(home)),
+        ("npm", Tool::Npm, env_dir("npm_config_cache").or_else(|| home_join(home, ".npm"))),
+        ("pip", Tool::Pip, pip_cache_dir(home)),
+        ("go", Tool::Go, go_mod_cache(home).map(|dir| dir.join("cache"))),
+        ("gradle", Tool::Gradle, env_dir("GRADLE_USER_HOME").or_else(|| home_join(home, ".gradle")).map(|dir| dir.join("caches"))),
+        ("maven", Tool::Maven, home_join(home, ".m2").map(|dir| dir.join("repository"))),
+    ]
+    .into_iter()
+    .filter_map(|(name, tool, path)| Some((name, tool, path?)))
+    .collect();
+
+    let projects = Arc::new(Projects {
+        roots: config.project_roots.clone(),
+        max_depth: config.max_depth,
+        skip: caches.iter().map(|(_, _, path)| path.clone()).chain(cargo_home(home)).chain(go_mod_cache(home)).collect(),
+        lockfiles: OnceLock::new(),
+    });
+
+    caches
+        .into_iter()
+        .map(|(name, tool, path)| (name, path, DevCache { tool, projects: projects.clone() }))
+        .collect()
+}
+
+impl DevCache {
+    // 生成缓存目录的规则；锁文件中的名称无法组成有效规则时不清理这个目录
+    pub fn rules(&self) -> RuleList {
+        let lockfiles = self.projects.lockfiles();
+        let (mut rules, exclude): (Vec<RuleConfig>, Vec<String>) = match self.tool {
+            // 解压后的源码和下载的 .crate 文件，只保留 Cargo.lock 中用到的版本
+            Tool::Cargo => {
+                let packages = lockfiles_named(lockfiles, "Cargo.lock").flat_map(cargo_packages);
+                let exclude = packages
+                    .flat_map(|crate_version| [
+                        format!("/registry/src/*/{}/", globset::escape(&crate_version)),
+                        format!("/registry/cache/*/{}.crate", globset::escape(&crate_version)),
+                    ])
+                    .collect();
+                (vec![rule("cargo-unused-crate", &["/registry/src/*/*/", "/registry/cache/*/*.crate"], 0)], exclude)
+            }
+            // _cacache 按内容哈希存放，package-lock.json 的 integrity 指向的内容保留
+            Tool::Npm => {
+                let exclude = lockfiles_named(lockfiles, "package-lock.json")
+                    .flat_map(npm_integrities)
+                    .filter_map(|integrity| npm_content_path(&integrity))
+                    .map(|path| format!("/_cacache/content-v2/{}", path))
+                    .collect();
+                (vec![rule("npm-unused-content", &["/_cacache/content-v2/"], 0), rule("npm-tmp", &["/_cacache/tmp/"], 0)], exclude)
+            }
+            // pip 没有锁文件，HTTP 缓存和本地构建的 wheel 按时间清理
+            Tool::Pip => (vec![rule("pip-stale-cache", &["/http/", "/http-v2/", "/wheels/"], 30)], Vec::new()),
+            // 模块下载缓存中 go.sum 没有引用的版本，以及版本控制仓库缓存
+            Tool::Go => {
+                let exclude = lockfiles_named(lockfiles, "go.sum")
+                    .flat_map(go_modules)
+                    .map(|(module, version)| {
+                        format!("/download/{}/@v/{}.*", globset::escape(&module), globset::escape(&version))
+                    })
+                    .collect();
+                (vec![rule("go-unused-module", &["/download/**/@v/*"], 0), rule("go-vcs-cache", &["/vcs/"], 30)], exclude)
+            }
+            // 依赖缓存中没有被 gradle.lockfile 锁定的版本，以及旧的构建缓存和各 Gradle 版本的缓存
+            Tool::Gradle => {
+                let exclude = lockfiles_named(lockfiles, "gradle.lockfile")
+                    .flat_map(gradle_dependencies)
+                    .map(|coordinates| format!("/modules-2/files-2.1/{}/", globset::escape(&coordinates)))
+                    .collect();
+                let rules = vec![
+                    rule("gradle-unused-dependency", &["/modules-2/files-2.1/*/*/*/"], 30),
+                    rule("gradle-stale-cache", &["/transforms-*/", "/build-cache-*/", "/[0-9]*.[0-9]*/"], 30),
+                ];
+                (rules, exclude)
+            }
+            // 下载失败留下的标记文件和旧的快照版本
+            Tool::Maven => {
+                let rules = vec![
+                    rule("maven-failed-download", &["*.lastUpdated"], 0),
+                    rule("maven-stale-snapshot", &["*-SNAPSHOT/"], 30),
+                ];
+                (rules, Vec::new())
+            }
+        };
+
+        if !exclude.is_empty() {
+            rules.push(RuleConfig { name: None, include: Vec::new(), exclude, min_age_days: 0, min_size_kb: 0 });
+        }
+        RuleList::try_from(rules).unwrap_or_default()
+    }
+}
+
+fn rule(name: &str, include: &[&str], min_age_days: u64) -> RuleConfig {
+    RuleConfig {
+        name: Some(name.to_string()),
+        include: include.iter().map(|pattern| pattern.to_string()).collect(),
+        exclude: Vec::new(),
+        min_age_days,
+        min_size_kb: 0,
+    }
+}
+
+impl Projects {
+    fn lockfiles(&self) -> &[PathBuf] {
+        self.lockfiles.get_or_init(|| {
+            let mut found = Vec::new();
+            for root in &self.roots {
+                self.find_lockfiles(root, 0, &mut found);
+            }
+            found
+        })
+    }
+
+    // 不跟随符号链接，跳过隐藏目录和常见的构建输出目录
+    fn find_lockfiles(&self, dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            let path = entry.path();
+
+            if file_type.is_file() && LOCKFILE_NAMES.contains(&name.as_ref()) {
+                found.push(path);
+            } else if file_type.is_dir()
+                && depth < self.max_depth
+                && !name.starts_with('.')
+                && !SKIPPED_DIR_NAMES.contains(&name.as_ref())
+                && !self.skip.contains(&path)
+            {
+                self.find_lockfiles(&path, depth + 1, found);
+            }
+        }
+    }
+}
+
+fn lockfiles_named<'a>(lockfiles: &'a [PathBuf], name: &'a str) -> impl Iterator<Item = String> + 'a {
+    lockfiles
+        .iter()
+        .filter(move |path| path.file_name().is_some_and(|file_name| file_name == name))
+        .filter_map(|path| fs::read_to_string(path).ok())
+}
+
+#[derive(Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<CargoPackage>,
+}
+
+#[derive(Deserialize)]
+struct CargoPackage {
+    name: String,
+    version: String,
+    source: Option<String>,
+}
+
+// Cargo.lock 中来自注册表的包，返回 "名称-版本"，与缓存中的目录名和文件名一致
+fn cargo_packages(content: String) -> Vec<String> {
+    let Ok(lock) = toml::from_str::<CargoLock>(&content) else {
+        return Vec::new();
+    };
+    lock.package
+        .into_iter()
+        .filter(|package| {
+            package.source.as_deref().is_some_and(|source| source.starts_with("registry+") || source.starts_with("sparse+"))
+        })
+        .map(|package| format!("{}-{}", package.name, package.version))
+        .collect()
+}
+
+// package-lock.json 各版本格式中 integrity 出现的位置不同，递归收集所有 integrity 字段
+fn npm_integrities(content: String) -> Vec<String> {
+    fn collect(value: &serde_json::Value, integrities: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, value) in map {
+                    match value {
+                        serde_json::Value::String(integrity) if key == "integrity" => {
+                            integrities.extend(integrity.split_whitespace().map(str::to_string));
+                        }
+                        _ => collect(value, integrities),
+                    }
+                }
+            }
+            serde_json::Value::Array(values) => values.iter().for_each(|value| collect(value, integrities)),
+            _ => {}
+        }
+    }
+
+    let mut integrities = Vec::new();
+    if let Ok(value) = serde_json::from_str(&content) {
+        collect(&value, &mut integrities);
+    }
+    integrities
+}
+
+// "sha512-<base64>" 对应 content-v2/sha512/<hex 前 2 位>/<3-4 位>/<其余>
+fn npm_content_path(integrity: &str) -> Option<String> {
+    let (algorithm, digest) = integrity.split_once('-')?;
+    if !algorithm.bytes().all(|b| b.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let hex: String = decode_base64(digest)?.iter().map(|byte| format!("{:02x}", byte)).collect();
+    if hex.len() < 5 {
+        return None;
+    }
+    Some(format!("{}/{}/{}/{}", algorithm, &hex[..2], &hex[2..4], &hex[4..]))
+}
+
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in text.trim_end_matches('=').bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6 | u32::from(value)) & 0xffff;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+// go.sum 每行为 "模块 版本[/go.mod] 哈希"；缓存路径中的大写字母转义为 ! 加小写字母
+fn go_modules(content: String) -> Vec<(String, String)> {
+    let escape = |text: &str| {
+        let mut escaped = String::with_capacity(text.len());
+        for c in text.chars() {
+            if c.is_ascii_uppercase() {
+                escaped.push('!');
+            }
+            escaped.push(c.to_ascii_lowercase());
+        }
+        escaped
+    };
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let module = fields.next()?;
+            let version = fields.next()?;
+            let version = version.strip_suffix("/go.mod").unwrap_or(version);
+            Some((escape(module), escape(version)))
+        })
+        .collect()
+}
+
+// gradle.lockfile 每行为 "group:artifact:version=配置列表"，返回缓存中对应的 group/artifact/version
+fn gradle_dependencies(content: String) -> Vec<String> {
+    content
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (coordinates, _) = line.split_once('=')?;
+            let mut parts = coordinates.split(':');
+            let (group, artifact, version) = (parts.next()?, parts.next()?, parts.next()?);
+            Some(format!("{}/{}/{}", group, artifact, version))
+        })
+        .collect()
+}
+
+fn cargo_home(home: Option<&Path>) -> Option<PathBuf> {
+    env_dir("CARGO_HOME").or_else(|| home_join(home, ".cargo"))
+}
+
+fn pip_cache_dir(home: Option<&Path>) -> Option<PathBuf> {
+    env_dir("PIP_CACHE_DIR")
+        .or_else(|| env_dir("XDG_CACHE_HOME").map(|dir| dir.join("pip")))
+        .or_else(|| home_join(home, ".cache").map(|dir| dir.join("pip")))
+}
+
+// GOMODCACHE 未设置时为 GOPATH 中第一项下的 pkg/mod
+fn go_mod_cache(home: Option<&Path>) -> Option<PathBuf> {
+    env_dir("GOMODCACHE")
+        .or_else(|| {
+            env::var_os("GOPATH")
+                .and_then(|paths| env::split_paths(&paths).next())
+                .filter(|dir| dir.is_absolute())
+                .map(|dir| dir.join("pkg").join("mod"))
+        })
+        .or_else(|| home_join(home, "go").map(|dir| dir.join("pkg").join("mod")))
+}
+
+fn env_dir(name: &str) -> Option<PathBuf> {
+    env::var_os(name).map(PathBuf::from).filter(|dir| dir.is_absolute())
+}
+
+fn home_join(home: Option<&Path>, name: &str) -> Option<PathBuf> {
+    home.map(|home| home.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cargo_registry_packages() {
+        let lock = r#"
+version = 3
+
+[[package]]
+name = "serde"
+version = "1.0.200"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "libc"
+version = "0.2.155"
+source = "sparse+https://index.crates.io/"
+
+[[package]]
+name = "local"
+version = "0.1.0"
+
+[[package]]
+name = "forked"
+version = "0.3.0"
+source = "git+https://example.com/forked#abc"
+"#;
+        assert_eq!(cargo_packages(lock.to_string()), ["serde-1.0.200", "libc-0.2.155"]);
+        assert!(cargo_packages("[[package]]\nname = ".to_string()).is_empty());
+        assert!(cargo_packages("[[package]]\nname = \"x\"\n".to_string()).is_empty());
+    }
+
+    #[test]
+    fn npm_integrity_paths() {
+        let lock = r#"{"packages": {"node_modules/a": {"integrity": "sha512-AAECAwQ= sha1-/w=="}},
+                       "dependencies": {"b": {"integrity": "sha1-AAECAwQ="}}}"#;
+        let mut integrities = npm_integrities(lock.to_string());
+        integrities.sort();
+        assert_eq!(integrities, ["sha1-/w==", "sha1-AAECAwQ=", "sha512-AAECAwQ="]);
+        assert!(npm_integrities("{not json".to_string()).is_empty());
+
+        assert_eq!(npm_content_path("sha512-AAECAwQ=").as_deref(), Some("sha512/00/01/020304"));
+        // 摘要太短、算法名不是单个目录名或 base64 无效时不生成路径
+        assert_eq!(npm_content_path("sha1-/w=="), None);
+        assert_eq!(npm_content_path("../x-AAECAwQ="), None);
+        assert_eq!(npm_content_path("sha512-AAEC*wQ="), None);
+        assert_eq!(npm_content_path("sha512"), None);
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(decode_base64("aGVsbG8=").unwrap(), b"hello");
+        assert_eq!(decode_base64("aGVsbG8h").unwrap(), b"hello!");
+        assert_eq!(decode_base64("").unwrap(), b"");
+        assert_eq!(decode_base64("aGV-bG8="), None);
+    }
+
+    #[test]
+    fn go_sum_modules_are_escaped() {
+        let sum = "github.com/BurntSushi/toml v1.3.2 h1:abc=\n\
+                   github.com/BurntSushi/toml v1.3.2/go.mod h1:def=\n\
+                   golang.org/x/text v0.14.0-RC1 h1:ghi=\n\
+                   incomplete\n";
+        assert_eq!(go_modules(sum.to_string()), [
+            ("github.com/!burnt!sushi/toml".to_string(), "v1.3.2".to_string()),
+            ("github.com/!burnt!sushi/toml".to_string(), "v1.3.2".to_string()),
+            ("golang.org/x/text".to_string(), "v0.14.0-!r!c1".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn gradle_lockfile_dependencies() {
+        let lockfile = "# This is a Gradle generated file\n\
+                        com.google.guava:guava:33.0.0-jre=compileClasspath,runtimeClasspath\n\
+                        org.slf4j:slf4j-api:2.0.9=runtimeClasspath\n\
+                        empty=annotationProcessor\n\
+                        broken:line\n";
+        assert_eq!(gradle_dependencies(lockfile.to_string()),
+            ["com.google.guava/guava/33.0.0-jre", "org.slf4j/slf4j-api/2.0.9"]);
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    fmt, io,
+    path::PathBuf,
+    sync::mpsc,
+};
+
+use crate::{Action, format_size, inuse::SkipReason};
+
+pub type EventSender = mpsc::Sender<Event>;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    ReleaseMemory,
+    Scan,
+    Clean,
+    RecycleBin,
+}
+
+// 后台任务发给界面、命令行输出和通知的事件
+pub enum Event {
+    PhaseStarted(Phase),
+    // count/bytes 的含义取决于阶段：进程数、计划项数或删除项数
+    PhaseFinished { phase: Phase, count: usize, bytes: u64 },
+    Progress(String),
+    Planned { path: PathBuf, action: Action, category: &'static str, rule: String, bytes: u64 },
+    FileDeleted { path: PathBuf, category: &'static str, bytes: u64 },
+    DirDeleted { path: PathBuf, bytes: u64 },
+    DeleteFailed { path: PathBuf, error: io::Error },
+    // 轮转日志压缩为 .gz 后的大小，以及截断前的大小
+    Compressed { path: PathBuf, bytes: u64, compressed: u64 },
+    Truncated { path: PathBuf, bytes: u64 },
+    ActionFailed { path: PathBuf, action: Action, error: io::Error },
+    // 正在使用或特殊类型的文件，计划和执行时都会跳过
+    Skipped { path: PathBuf, reason: SkipReason },
+    Quarantined { path: PathBuf, bytes: u64 },
+    QuarantineFailed { path: PathBuf, error: io::Error },
+    Restored { path: PathBuf, bytes: u64 },
+    RestoreFailed { path: PathBuf, error: io::Error },
+    RecycleBinEmptied { bytes: u64 },
+    RecycleBinFailed(io::Error),
+    // 按目标汇总；dry_run 为 true 时是预计值，quarantined 为移入隔离区而没有释放的字节数
+    TargetTotal { target: String, count: usize, bytes: u64, quarantined: u64, dry_run: bool },
+    CompressionTotal { count: usize, saved: u64 },
+    FilesystemSpace { root: PathBuf, before: u64, after: u64 },
+    // 释放内存阶段：label 为"释放前"或"释放后"，各项操作的释放量按操作前后的占用之差计算；
+    // meminfo、各项操作的释放量和内存整理只在 Linux 上报告
+    #[cfg_attr(windows, allow(dead_code))]
+    MemoryInfo { label: &'static str, total: u64, available: u64, free: u64, cached: u64 },
+    #[cfg_attr(windows, allow(dead_code))]
+    MemoryReclaimed { action: String, bytes: u64 },
+    // Windows 上为工作集，Linux 上为常驻内存（VmRSS）
+    ProcessTrimmed { pid: u32, name: String, before: u64, after: u64 },
+    #[cfg_attr(windows, allow(dead_code))]
+    MemoryCompacted { before: u64, after: u64 },
+    MemoryActionFailed { action: String, error: io::Error },
+}
+
+impl Event {
+    pub fn is_error(&self) -> bool {
+        matches!(self, Event::DeleteFailed { .. } | Event::ActionFailed { .. } | Event::QuarantineFailed { .. }
+            | Event::RestoreFailed { .. } | Event::RecycleBinFailed(_) | Event::MemoryActionFailed { .. })
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Phase::ReleaseMemory => "释放内存",
+            Phase::Scan => "扫描",
+            Phase::Clean => "清理",
+            Phase::RecycleBin => "清空回收站",
+        })
+    }
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Event::PhaseStarted(Phase::ReleaseMemory) => write!(f, "正在释放内存..."),
+            Event::PhaseStarted(Phase::Clean) => write!(f, "正在清理中..."),
+            Event::PhaseStarted(phase) => write!(f, "正在{}...", phase),
+            Event::PhaseFinished { phase: Phase::ReleaseMemory, count, bytes: 0 } => {
+                write!(f, "内存释放完成! 共整理了 {} 个进程。", count)
+            }
+            Event::PhaseFinished { phase: Phase::ReleaseMemory, count, bytes } => {
+                write!(f, "内存释放完成! 共释放 {} ({} 个进程)。", format_size(*bytes), count)
+            }
+            Event::PhaseFinished { phase: Phase::Scan, count, bytes } => {
+                write!(f, "扫描完成, 共 {} 项, 预计可释放 {}", count, format_size(*bytes))
+            }
+            Event::PhaseFinished { phase, count, bytes } => {
+                write!(f, "{}完成! 总共清理了 {} 个文件/目录, 释放了 {}。", phase, count, format_size(*bytes))
+            }
+            Event::Progress(msg) => f.write_str(msg),
+            Event::Planned { path, action, category, rule, bytes } => {
+                write!(f, "计划{}{}: {} ({}, 规则: {})", action.verb(), category, path.display(), format_size(*bytes), rule)
+            }
+            Event::FileDeleted { path, category, bytes } => {
+                write!(f, "已删除{}: {:?} ({})", category, path, format_size(*bytes))
+            }
+            Event::DirDeleted { path, bytes } => write!(f, "已删除目录: {:?} ({})", path, format_size(*bytes)),
+            Event::DeleteFailed { path, error } => write!(f, "删除失败: {:?} ({})", path, error),
+            Event::Compressed { path, bytes, compressed } => {
+                write!(f, "已压缩: {:?} ({} -> {})", path, format_size(*bytes), format_size(*compressed))
+            }
+            Event::Truncated { path, bytes } => write!(f, "已截断: {:?} ({})", path, format_size(*bytes)),
+            Event::ActionFailed { path, action, error } => write!(f, "{}失败: {:?} ({})", action.verb(), path, error),
+            Event::Skipped { path, reason } => write!(f, "已跳过: {:?} ({})", path, reason),
+            Event::Quarantined { path, bytes } => write!(f, "已移入隔离区: {:?} ({})", path, format_size(*bytes)),
+            Event::QuarantineFailed { path, error } => {
+                write!(f, "无法创建隔离区 {}, 已停止清理 ({})", path.display(), error)
+            }
+            Event::Restored { path, bytes } => write!(f, "已恢复: {:?} ({})", path, format_size(*bytes)),
+            Event::RestoreFailed { path, error } => write!(f, "恢复失败: {:?} ({})", path, error),
+            Event::RecycleBinEmptied { bytes } => write!(f, "已清空回收站 ({})", format_size(*bytes)),
+            Event::RecycleBinFailed(error) => write!(f, "清空回收站失败 ({})", error),
+            Event::TargetTotal { target, count, bytes, dry_run: true, .. } => {
+                write!(f, "预览: 目标 {} 共 {} 项, 预计可释放 {}", target, count, format_size(*bytes))
+            }
+            Event::TargetTotal { target, count, bytes, quarantined: 0, dry_run: false } => {
+                write!(f, "目标 {}: 删除 {} 项, 释放 {}", target, count, format_size(*bytes))
+            }
+            Event::TargetTotal { target, count, bytes, quarantined, dry_run: false } => {
+                write!(f, "目标 {}: 处理 {} 项, 移入隔离区 {}, 释放 {}", target, count,
+                    format_size(*quarantined), format_size(*bytes))
+            }
+            Event::CompressionTotal { count, saved } => {
+                write!(f, "压缩了 {} 个日志文件, 节省 {}", count, format_size(*saved))
+            }
+            Event::FilesystemSpace { root, before, after } => {
+                write!(f, "文件系统 {}: 可用空间 {} -> {} (+{})", root.display(),
+                    format_size(*before), format_size(*after), format_size(after.saturating_sub(*before)))
+            }
+            Event::MemoryInfo { label, total, available, free, cached } => {
+                write!(f, "内存 ({}): 可用 {} / 共 {}, 空闲 {}, 缓存 {}", label,
+                    format_size(*available), format_size(*total), format_size(*free), format_size(*cached))
+            }
+            Event::MemoryReclaimed { action, bytes } => write!(f, "{}: 释放 {}", action, format_size(*bytes)),
+            Event::ProcessTrimmed { pid, name, before, after } => {
+                write!(f, "进程 {} ({}): 内存 {} -> {} (释放 {})", name, pid,
+                    format_size(*before), format_size(*after), format_size(before.saturating_sub(*after)))
+            }
+            Event::MemoryCompacted { before, after } => {
+                write!(f, "整理内存碎片: 2 MB 以上的连续空闲内存 {} -> {}", format_size(*before), format_size(*after))
+            }
+            Event::MemoryActionFailed { action, error } => write!(f, "内存操作失败: {} ({})", action, error),
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    collections::HashSet,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+// 正在被进程使用的文件，按 (设备号, inode) 记录
+#[derive(Default)]
+pub struct OpenFiles {
+    inodes: HashSet<(u64, u64)>,
+}
+
+// 计划中的项不能删除的原因，路径为目录中实际命中的项
+pub enum SkipReason {
+    InUse(PathBuf),
+    Special(PathBuf, &'static str),
+    Locked(PathBuf), // 目标的锁文件表明所属程序正在运行
+    Irreversible,    // 隔离模式下只执行可以恢复的操作
+}
+
+impl OpenFiles {
+    // 遍历 /proc/*/fd、/proc/*/maps 和 /proc/*/cwd；无权读取的进程会被忽略，
+    // 所以普通用户运行时只能看到自己的进程
+    #[cfg(target_os = "linux")]
+    pub fn collect() -> Self {
+        use std::os::unix::fs::MetadataExt;
+
+        let mut inodes = HashSet::new();
+        let Ok(processes) = fs::read_dir("/proc") else {
+            return Self { inodes };
+        };
+
+        for process in processes.filter_map(Result::ok) {
+            if !process.file_name().to_str().is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit())) {
+                continue;
+            }
+            let proc_dir = process.path();
+
+            // fd 和 cwd 是指向实际文件的链接，metadata 会跟随到目标（包括已删除的文件）
+            if let Ok(fds) = fs::read_dir(proc_dir.join("fd")) {
+                for fd in fds.filter_map(Result::ok) {
+                    if let Ok(metadata) = fs::metadata(fd.path()) {
+                        inodes.insert((metadata.dev(), metadata.ino()));
+                    }
+                }
+            }
+            if let Ok(metadata) = fs::metadata(proc_dir.join("cwd")) {
+                inodes.insert((metadata.dev(), metadata.ino()));
+            }
+            if let Ok(maps) = fs::read_to_string(proc_dir.join("maps")) {
+                inodes.extend(maps.lines().filter_map(parse_maps_line));
+            }
+        }
+
+        Self { inodes }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn collect() -> Self {
+        Self::default()
+    }
+
+    // 检查计划中的一项能否删除；目录会递归检查其中的所有项
+    pub fn check(&self, path: &Path) -> Option<SkipReason> {
+        if cfg!(not(unix)) {
+            return None;
+        }
+
+        let metadata = fs::symlink_metadata(path).ok()?;
+        if let Some(kind) = special_file_kind(&metadata) {
+            return Some(SkipReason::Special(path.to_path_buf(), kind));
+        }
+        if self.is_open(&metadata) {
+            return Some(SkipReason::InUse(path.to_path_buf()));
+        }
+
+        if metadata.is_dir() {
+            for entry in fs::read_dir(path).ok()?.filter_map(Result::ok) {
+                if let Some(reason) = self.check(&entry.path()) {
+                    return Some(reason);
+                }
+            }
+        }
+        None
+    }
+
+    #[cfg(unix)]
+    fn is_open(&self, metadata: &fs::Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        self.inodes.contains(&(metadata.dev(), metadata.ino()))
+    }
+
+    #[cfg(not(unix))]
+    fn is_open(&self, _metadata: &fs::Metadata) -> bool {
+        false
+    }
+}
+
+// 浏览器等程序的锁文件是指向 "主机名-PID"（Chromium）或 "地址:+PID"（Firefox）的符号链接，
+// 链接存在且进程仍在运行时视为被占用；无法解析出 PID 时也按占用处理
+#[cfg(unix)]
+pub fn lock_is_held(lock: &Path) -> bool {
+    let Ok(owner) = fs::read_link(lock) else {
+        return false;
+    };
+    let owner = owner.to_string_lossy();
+    let pid = owner.rsplit(|c: char| !c.is_ascii_digit()).next().and_then(|pid| pid.parse::<libc::pid_t>().ok());
+    let Some(pid) = pid.filter(|pid| *pid > 0) else {
+        return true;
+    };
+    // 信号 0 只检查进程是否存在，EPERM 表示进程存在但属于其他用户
+    let alive = unsafe { libc::kill(pid, 0) } == 0;
+    alive || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+pub fn lock_is_held(_lock: &Path) -> bool {
+    false
+}
+
+// maps 的格式为 "地址 权限 偏移 主:次设备号 inode 路径"，inode 为 0 表示匿名映射
+#[cfg(target_os = "linux")]
+fn parse_maps_line(line: &str) -> Option<(u64, u64)> {
+    let mut fields = line.split_whitespace().skip(3);
+    let (major, minor) = fields.next()?.split_once(':')?;
+    let inode: u64 = fields.next()?.parse().ok()?;
+    if inode == 0 {
+        return None;
+    }
+
+    let major = u32::from_str_radix(major, 16).ok()?;
+    let minor = u32::from_str_radix(minor, 16).ok()?;
+    Some((libc::makedev(major, minor), inode))
+}
+
+// 套接字、命名管道和设备节点无论规则如何都不删除
+#[cfg(unix)]
+fn special_file_kind(metadata: &fs::Metadata) -> Option<&'static str> {
+    use std::os::unix::fs::FileTypeExt;
+
+    let file_type = metadata.file_type();
+    if file_type.is_socket() {
+        Some("套接字")
+    } else if file_type.is_fifo() {
+        Some("命名管道")
+    } else if file_type.is_char_device() {
+        Some("字符设备")
+    } else if file_type.is_block_device() {
+        Some("块设备")
+    } else {
+        None
+    }
+}
+
+#[cfg(not(unix))]
+fn special_file_kind(_metadata: &fs::Metadata) -> Option<&'static str> {
+    None
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkipReason::InUse(path) => write!(f, "{} 正在被进程使用", path.display()),
+            SkipReason::Special(path, kind) => write!(f, "{} 是{}", path.display(), kind),
+            SkipReason::Locked(lock) => write!(f, "程序正在运行, 锁文件 {}", lock.display()),
+            SkipReason::Irreversible => f.write_str("隔离模式下不执行无法恢复的操作"),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn parse_maps_lines() {
+        let libc_dev = libc::makedev(0xfd, 0x01);
+        assert_eq!(
+            parse_maps_line("7f1c2e400000-7f1c2e428000 r--p 00000000 fd:01 1319003    /usr/lib/x86_64-linux-gnu/libc.so.6"),
+            Some((libc_dev, 1319003))
+        );
+        // 路径中的空格和已删除文件的后缀不影响设备号和 inode
+        assert_eq!(
+            parse_maps_line("7f00000000-7f00001000 r-xp 00001000 08:12 42    /home/user/My Files/app bin"),
+            Some((libc::makedev(0x08, 0x12), 42))
+        );
+        assert_eq!(
+            parse_maps_line("7f00000000-7f00001000 rw-s 00000000 00:1a 77    /dev/shm/buffer (deleted)"),
+            Some((libc::makedev(0x00, 0x1a), 77))
+        );
+        // 匿名映射和格式错误的行
+        assert_eq!(parse_maps_line("55d4c8a00000-55d4c8a21000 rw-p 00000000 00:00 0    [heap]"), None);
+        assert_eq!(parse_maps_line("7ffd1e9f0000-7ffd1ea11000 rw-p 00000000 00:00 0"), None);
+        assert_eq!(parse_maps_line("7f00000000-7f00001000 r--p 00000000 zz:01 5 /x"), None);
+        assert_eq!(parse_maps_line("garbage"), None);
+    }
+
+    #[test]
+    fn lock_owner_must_be_running() {
+        let temp = tempfile::tempdir().unwrap();
+        let lock = temp.path().join("SingletonLock");
+        assert!(!lock_is_held(&lock));
+
+        symlink(format!("host-{}", std::process::id()), &lock).unwrap();
+        assert!(lock_is_held(&lock));
+
+        // 超过 pid_max 的进程号不可能存在
+        let firefox_lock = temp.path().join("lock");
+        symlink("127.0.1.1:+999999999", &firefox_lock).unwrap();
+        assert!(!lock_is_held(&firefox_lock));
+
+        let unknown = temp.path().join("unknown");
+        symlink("no-pid-here", &unknown).unwrap();
+        assert!(lock_is_held(&unknown));
+
+        // 普通文件不是锁
+        let plain = temp.path().join("plain");
+        fs::write(&plain, "").unwrap();
+        assert!(!lock_is_held(&plain));
+    }
+
+    #[test]
+    fn special_files_are_never_deleted() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().join("dir");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("plain"), "").unwrap();
+        let open_files = OpenFiles::default();
+        assert!(open_files.check(&dir).is_none());
+
+        let fifo = dir.join("fifo");
+        let c_path = std::ffi::CString::new(fifo.to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+        assert!(matches!(open_files.check(&fifo), Some(SkipReason::Special(_, "命名管道"))));
+        // 目录中有特殊文件时整个目录跳过，原因指向该文件
+        assert!(matches!(open_files.check(&dir), Some(SkipReason::Special(path, _)) if path == fifo));
+
+        let socket = temp.path().join("socket");
+        let _listener = std::os::unix::net::UnixListener::bind(&socket).unwrap();
+        assert!(matches!(open_files.check(&socket), Some(SkipReason::Special(_, "套接字"))));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn open_files_are_in_use() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("open.log");
+        let _file = fs::File::create(&path).unwrap();
+        let closed = temp.path().join("closed.log");
+        fs::write(&closed, "").unwrap();
+
+        let open_files = OpenFiles::collect();
+        assert!(matches!(open_files.check(&path), Some(SkipReason::InUse(_))));
+        assert!(open_files.check(&closed).is_none());
+    }
+}
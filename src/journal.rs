@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{Action, PlanEntry, Target, config::JournalConfig};
+
+// systemd-journald 的日志目录：<dir>/<machine-id>/ 下的 system.journal、user-<UID>.journal 是正在写入的文件，
+// 文件名中带 @ 的是已归档的文件（.journal~ 是 journald 发现损坏后改名的文件）。
+// 清理只删除归档文件，先删超过 max_age_days 的，再从最旧的开始删到每台机器的总量不超过 max_size_mb
+pub struct JournalFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub machine_id: String,
+    pub boot_id: Option<String>, // 文件中最后一条记录所属的启动
+    pub archived: bool,
+    pub head: Option<SystemTime>, // 第一条和最后一条记录的时间
+    pub tail: Option<SystemTime>,
+}
+
+// 日志文件头（journal-def.h 中的 Header）中用到的字段偏移
+const SIGNATURE: &[u8; 8] = b"LPKSHHRH";
+const HEADER_LEN: usize = 208;
+const STATE_OFFSET: usize = 16;
+const MACHINE_ID_OFFSET: usize = 40;
+const TAIL_BOOT_ID_OFFSET: usize = 56;
+const HEAD_REALTIME_OFFSET: usize = 184;
+const TAIL_REALTIME_OFFSET: usize = 192;
+const STATE_ONLINE: u8 = 1;
+
+// 读取日志目录下各机器目录中的日志文件；读不到文件头的文件只按文件名判断是否已归档
+pub fn scan(dir: &Path) -> Vec<JournalFile> {
+    let Ok(machines) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files = Vec::new();
+    for machine in machines.filter_map(Result::ok) {
+        if !machine.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        let machine_name = machine.file_name().to_string_lossy().into_owned();
+        let Ok(entries) = fs::read_dir(machine.path()) else {
+            continue;
+        };
+
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            if !metadata.is_file() || !(name.ends_with(".journal") || name.ends_with(".journal~")) {
+                continue;
+            }
+
+            let path = entry.path();
+            let header = read_header(&path);
+            let online = header.as_ref().is_some_and(|header| header[STATE_OFFSET] == STATE_ONLINE);
+            files.push(JournalFile {
+                size: metadata.len(),
+                machine_id: header
+                    .as_ref()
+                    .map(|header| hex_id(&header[MACHINE_ID_OFFSET..MACHINE_ID_OFFSET + 16]))
+                    .unwrap_or_else(|| machine_name.clone()),
+                boot_id: header.as_ref().map(|header| hex_id(&header[TAIL_BOOT_ID_OFFSET..TAIL_BOOT_ID_OFFSET + 16])),
+                // 正在写入的文件状态为 ONLINE；文件名不带 @ 的文件随时可能被 journald 重新打开，同样不动
+                archived: name.contains('@') && !online,
+                head: header.as_ref().and_then(|header| realtime(header, HEAD_REALTIME_OFFSET)),
+                tail: header.as_ref().and_then(|header| realtime(header, TAIL_REALTIME_OFFSET)),
+                path,
+            });
+        }
+    }
+    files
+}
+
+// 按机器生成归档文件的清理计划
+pub fn plan(target: &Target, config: &JournalConfig) -> Vec<PlanEntry> {
+    let files = scan(&target.path);
+    let max_age = Duration::from_secs(config.max_age_days * 24 * 3600);
+    let max_size = config.max_size_mb * 1024 * 1024;
+
+    let mut machine_ids: Vec<&str> = files.iter().map(|file| file.machine_id.as_str()).collect();
+    machine_ids.sort();
+    machine_ids.dedup();
+
+    let mut plan = Vec::new();
+    for machine_id in machine_ids {
+        let machine_files: Vec<&JournalFile> = files.iter().filter(|file| file.machine_id == machine_id).collect();
+        let mut total: u64 = machine_files.iter().map(|file| file.size).sum();
+        let mut archived: Vec<&JournalFile> = machine_files.into_iter().filter(|file| file.archived).collect();
+        archived.sort_by_key(|file| file.last_time());
+
+        for file in archived {
+            let time = file.last_time();
+            let expired = config.max_age_days > 0
+                && time.and_then(|time| SystemTime::now().duration_since(time).ok()).is_some_and(|age| age >= max_age);
+            let over_size = config.max_size_mb > 0 && total > max_size;
+            if !expired && !over_size {
+                continue;
+            }
+
+            total -= file.size;
+            plan.push(PlanEntry {
+                target: target.name.clone(),
+                root: target.path.clone(),
+                path: file.path.clone(),
+                size: file.size,
+                is_dir: false,
+                rule: if expired { "journal-age" } else { "journal-size" }.to_string(),
+                category: "系统日志",
+                time,
+                action: Action::Delete,
+            });
+        }
+    }
+    plan
+}
+
+impl JournalFile {
+    // 最后一条记录的时间，读不到文件头时用修改时间
+    pub fn last_time(&self) -> Option<SystemTime> {
+        self.tail.or_else(|| fs::metadata(&self.path).and_then(|metadata| metadata.modified()).ok())
+    }
+}
+
+fn read_header(path: &Path) -> Option<[u8; HEADER_LEN]> {
+    let mut header = [0; HEADER_LEN];
+    fs::File::open(path).ok()?.read_exact(&mut header).ok()?;
+    (&header[..8] == SIGNATURE).then_some(header)
+}
+
+// 记录时间为自 Unix 纪元起的微秒数，0 表示文件中还没有记录
+fn realtime(header: &[u8; HEADER_LEN], offset: usize) -> Option<SystemTime> {
+    let micros = u64::from_le_bytes(header[offset..offset + 8].try_into().ok()?);
+    (micros > 0).then(|| UNIX_EPOCH + Duration::from_micros(micros))
+}
+
+fn hex_id(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// 当前系统的 machine-id 和 boot-id，用于在用量报告中标记
+pub fn current_machine_id() -> Option<String> {
+    fs::read_to_string("/etc/machine-id").ok().map(|id| id.trim().to_string())
+}
+
+pub fn current_boot_id() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/random/boot_id").ok().map(|id| id.trim().replace('-', ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+    const OFFLINE: u8 = 0;
+
+    // 构造文件头：机器 ID 和启动 ID 的每个字节分别为 machine、boot，最后一条记录在 days_ago 天前
+    fn header(state: u8, machine: u8, boot: u8, days_ago: u32) -> Vec<u8> {
+        let mut header = vec![0; HEADER_LEN];
+        header[..8].copy_from_slice(SIGNATURE);
+        header[STATE_OFFSET] = state;
+        header[MACHINE_ID_OFFSET..MACHINE_ID_OFFSET + 16].fill(machine);
+        header[TAIL_BOOT_ID_OFFSET..TAIL_BOOT_ID_OFFSET + 16].fill(boot);
+        let tail = (SystemTime::now() - DAY * days_ago).duration_since(UNIX_EPOCH).unwrap().as_micros() as u64;
+        header[HEAD_REALTIME_OFFSET..HEAD_REALTIME_OFFSET + 8].copy_from_slice(&(tail - 1000).to_le_bytes());
+        header[TAIL_REALTIME_OFFSET..TAIL_REALTIME_OFFSET + 8].copy_from_slice(&tail.to_le_bytes());
+        header
+    }
+
+    fn write(path: &Path, mut content: Vec<u8>, size: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        content.resize(size.max(content.len()), 0);
+        fs::write(path, content).unwrap();
+    }
+
+    fn scanned(dir: &Path) -> Vec<(String, bool)> {
+        let mut files: Vec<(String, bool)> = scan(dir)
+            .into_iter()
+            .map(|file| (file.path.file_name().unwrap().to_string_lossy().into_owned(), file.archived))
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn header_fields() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("m/system@0001.journal");
+        write(&path, header(OFFLINE, 0xab, 0x01, 3), 0);
+
+        let files = scan(temp.path());
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].machine_id, "ab".repeat(16));
+        assert_eq!(files[0].boot_id.as_deref(), Some("01".repeat(16).as_str()));
+        let age = SystemTime::now().duration_since(files[0].tail.unwrap()).unwrap();
+        assert!(age >= DAY * 3 && age < DAY * 3 + Duration::from_secs(60));
+        assert!(files[0].head.unwrap() < files[0].tail.unwrap());
+    }
+
+    #[test]
+    fn only_offline_files_with_at_are_archived() {
+        let temp = tempfile::tempdir().unwrap();
+        let machine = temp.path().join("0123456789abcdef0123456789abcdef");
+        write(&machine.join("system.journal"), header(STATE_ONLINE, 1, 1, 0), 0);
+        write(&machine.join("user-1000.journal"), header(OFFLINE, 1, 1, 0), 0);
+        write(&machine.join("system@abc-0001-0002.journal"), header(OFFLINE, 1, 1, 0), 0);
+        // 名称带 @ 但仍是 ONLINE 状态的文件正在被写入
+        write(&machine.join("system@abc-0003-0004.journal"), header(STATE_ONLINE, 1, 1, 0), 0);
+        // journald 改名的损坏文件读不到文件头，按名称判断，机器 ID 取目录名
+        write(&machine.join("system@0005.journal~"), b"garbage".to_vec(), 0);
+        write(&machine.join("notes.txt"), Vec::new(), 0);
+
+        assert_eq!(scanned(temp.path()), [
+            ("system.journal".to_string(), false),
+            ("system@0005.journal~".to_string(), true),
+            ("system@abc-0001-0002.journal".to_string(), true),
+            ("system@abc-0003-0004.journal".to_string(), false),
+            ("user-1000.journal".to_string(), false),
+        ]);
+        let corrupt = scan(temp.path()).into_iter().find(|file| file.path.ends_with("system@0005.journal~")).unwrap();
+        assert_eq!(corrupt.machine_id, "0123456789abcdef0123456789abcdef");
+        assert_eq!(corrupt.tail, None);
+    }
+
+    #[test]
+    fn plan_removes_expired_then_oldest_over_size() {
+        let temp = tempfile::tempdir().unwrap();
+        let machine = temp.path().join("m");
+        write(&machine.join("system@1.journal"), header(OFFLINE, 1, 1, 60), 1000);
+        write(&machine.join("system@2.journal"), header(OFFLINE, 1, 1, 10), 600 * 1024);
+        write(&machine.join("system@3.journal"), header(OFFLINE, 1, 1, 5), 600 * 1024);
+        write(&machine.join("system.journal"), header(STATE_ONLINE, 1, 1, 0), 100 * 1024);
+        // 另一台机器的文件单独计算总量
+        write(&temp.path().join("other/system@4.journal"), header(OFFLINE, 2, 1, 10), 600 * 1024);
+
+        let config = JournalConfig { dir: temp.path().to_path_buf(), max_age_days: 30, max_size_mb: 1, ..JournalConfig::default() };
+        let target = Target::new("journal", temp.path()).with_journal(config.clone());
+        let planned: Vec<(String, String)> = plan(&target, &config)
+            .into_iter()
+            .map(|entry| (entry.path.file_name().unwrap().to_string_lossy().into_owned(), entry.rule))
+            .collect();
+        // 先删过期的，剩余 1.3 MB 仍超过 1 MB，再删最旧的一个
+        assert_eq!(planned, [
+            ("system@1.journal".to_string(), "journal-age".to_string()),
+            ("system@2.journal".to_string(), "journal-size".to_string()),
+        ]);
+    }
+}
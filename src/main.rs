@@ -1,9 +1,33 @@
+mod cli;
+mod browser;
+mod compress;
+mod config;
+mod container;
+mod devcache;
+mod event;
+mod inuse;
+mod journal;
+mod mounts;
+mod pkgcache;
+mod platform;
+mod quarantine;
+#[cfg(unix)]
+mod reclaim;
+mod rooted;
+mod rotation;
+mod rules;
+mod space;
+mod sweep;
+#[cfg(unix)]
+mod trash;
+mod xdg;
+
+use clap::Parser;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self as terminal_event, DisableMouseCapture, EnableMouseCapture, Event as TerminalEvent, KeyCode},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use notify_rust::Notification;
 use ratatui::{
     prelude::{CrosstermBackend, Terminal, Color, Style},
     widgets::{Block, Borders, List, ListItem, Paragraph},
@@ -14,166 +38,474 @@ use std::{
     env,
     fs,
     io::{self, Stdout},
+    collections::HashSet,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc, Arc,
     },
-    time::Duration,
-};
-use windows_sys::Win32::{
-    System::ProcessStatus::{
-        EmptyWorkingSet, EnumProcesses, GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS,
-    },
-    System::Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_VM_READ},
-    UI::Shell::{SHEmptyRecycleBinW, SHERB_NOCONFIRMATION, SHERB_NOPROGRESSUI, SHERB_NOSOUND},
+    time::{Duration, SystemTime},
 };
+use devcache::DevCache;
+use config::{AgeBy, CompressFormat, Config, ContainerConfig, JournalConfig, LogScanConfig, MemoryConfig, NotificationConfig, RuleSet, ScanConfig, TargetConfig};
+use event::{Event, EventSender, Phase};
+use inuse::{OpenFiles, SkipReason};
+use mounts::Mounts;
+use pkgcache::PackageCache;
+use platform::{KnownFolder, Platform};
+use quarantine::Quarantine;
+use rules::{Rule, RuleList, Verdict};
+use space::FilesystemSpace;
+
+// 一个清理目标：名称用于命令行选择，路径为要扫描的目录
+#[derive(Clone)]
+struct Target {
+    name: String,
+    path: PathBuf,
+    rules: RuleList,
+    retention: Retention,
+    keep: Vec<PathBuf>, // 相对于目标目录，这些目录本身不会被删除，其中的内容照常清理
+    kind: TargetKind,
+    category: Option<&'static str>, // 计划中显示的类别，未设置时按目录名推断
+    breakdown: bool, // report 中按第一级子目录分别列出占用和可释放的空间
+    lock: Option<PathBuf>, // 所属程序的锁文件，程序运行时整个目标跳过
+}
+
+// 目标的计划方式：按规则遍历目录，或由专门的子系统生成计划
+#[derive(Clone)]
+enum TargetKind {
+    Directory,
+    DevCache(DevCache),      // 规则依赖项目锁文件，扫描时才生成
+    PackageCache(PackageCache), // 规则依赖已安装的包，扫描时才生成
+    Journal(JournalConfig),  // systemd 日志，只按归档文件清理
+    Container(ContainerConfig), // 容器引擎，路径为 API 套接字，通过 API 清理
+    RotatedLogs(LogScanConfig), // 轮转日志，按轮转组保留最新的几代，与日志扫描使用同一套策略
+}
+
+// 按年龄、大小和数量筛选计划项的策略，各目标和日志扫描共用
+#[derive(Clone, Copy, Default)]
+struct Retention {
+    min_age_days: u64,
+    age_by: AgeBy,
+    min_size: u64,
+    keep_newest: usize,
+}
+
+impl Target {
+    fn new(name: &str, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            name: name.to_string(),
+            rules: RuleList::preset(RuleSet::Auto, &path),
+            path,
+            retention: Retention::default(),
+            keep: Vec::new(),
+            kind: TargetKind::Directory,
+            category: None,
+            breakdown: false,
+            lock: None,
+        }
+    }
+
+    fn with_keep(mut self, keep: &[&str]) -> Self {
+        self.keep = keep.iter().map(PathBuf::from).collect();
+        self
+    }
+
+    fn with_rules(mut self, rules: RuleList) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    fn with_dev_cache(mut self, dev_cache: DevCache) -> Self {
+        self.kind = TargetKind::DevCache(dev_cache);
+        self.category = Some("开发工具缓存");
+        self
+    }
+
+    fn with_package_cache(mut self, package_cache: PackageCache) -> Self {
+        self.kind = TargetKind::PackageCache(package_cache);
+        self.category = Some("软件包缓存");
+        self
+    }
+
+    fn with_journal(mut self, config: JournalConfig) -> Self {
+        self.kind = TargetKind::Journal(config);
+        self
+    }
+
+    fn with_container(mut self, config: ContainerConfig) -> Self {
+        self.kind = TargetKind::Container(config);
+        self
+    }
+
+    fn with_rotated_logs(mut self, config: LogScanConfig) -> Self {
+        self.kind = TargetKind::RotatedLogs(config);
+        self.rules = RuleList::rotated_logs();
+        self.category = Some("轮转日志");
+        self
+    }
+
+    fn with_min_age(mut self, days: u64) -> Self {
+        self.retention.min_age_days = days;
+        self
+    }
+
+    fn with_category(mut self, category: &'static str) -> Self {
+        self.category = Some(category);
+        self
+    }
+
+    fn with_breakdown(mut self) -> Self {
+        self.breakdown = true;
+        self
+    }
+
+    fn with_lock(mut self, lock: PathBuf) -> Self {
+        self.lock = Some(lock);
+        self
+    }
+
+    fn is_locked(&self) -> bool {
+        self.lock.as_deref().is_some_and(inuse::lock_is_held)
+    }
+
+    fn is_kept(&self, dir: &Path) -> bool {
+        dir.strip_prefix(&self.path).is_ok_and(|relative| self.keep.iter().any(|keep| keep == relative))
+    }
+
+    fn from_config(config: &TargetConfig, path: &Path) -> Self {
+        let rules = if config.rules.is_empty() { RuleList::preset(config.rule, path) } else { config.rules.clone() };
+        let mut target = Self::new(&config.name, path).with_rules(rules);
+        target.apply_config(config);
+        target
+    }
+
+    // 用配置中的设置代替目标的保留策略；没有设置的项保持原样，keep 追加到已有的保留目录之后
+    fn apply_config(&mut self, config: &TargetConfig) {
+        let retention = &mut self.retention;
+        retention.min_age_days = config.min_age_days.unwrap_or(retention.min_age_days);
+        retention.age_by = config.age_by.unwrap_or(retention.age_by);
+        retention.min_size = config.min_size_kb.map_or(retention.min_size, |kb| kb * 1024);
+        retention.keep_newest = config.keep_newest.unwrap_or(retention.keep_newest);
+        self.keep.extend(config.keep.iter().cloned());
+    }
+}
 
-// 通知配置常量
-const NOTIFICATION_ENABLED: bool = true;
-const NOTIFICATION_TIMEOUT: u64 = 5000;
+// 清理计划中的一项：预览模式下只展示，正常模式下按计划删除
+struct PlanEntry {
+    target: String,
+    root: PathBuf, // 扫描的根目录，删除时不能离开这个目录
+    path: PathBuf,
+    size: u64,
+    is_dir: bool, // 目录只在其中的内容清理完后删除，不会整体递归删除
+    rule: String,
+    category: &'static str,
+    time: Option<SystemTime>, // 按 age_by 取得的时间，目录取其中最新的时间
+    action: Action,
+}
 
-// 日志文件清理配置
-const LOG_SCAN_ENABLED: bool = true;
-const LOG_SCAN_DRIVES: &[&str] = &["C:", "D:", "E:"]; // 要扫描的驱动器
-const LOG_MAX_AGE_DAYS: u64 = 30; // 只清理超过30天的日志文件
-const LOG_MIN_SIZE_MB: u64 = 1; // 只清理大于1MB的日志文件
+// 对计划中的文件执行的操作；压缩和截断只用于日志扫描找到的轮转日志
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Delete,
+    Compress(CompressFormat),
+    Truncate,
+    Prune, // 通过容器引擎的 API 删除对象
+}
 
+impl Action {
+    fn verb(self) -> &'static str {
+        match self {
+            Action::Delete => "删除",
+            Action::Compress(_) => "压缩",
+            Action::Truncate => "截断",
+            Action::Prune => "清理",
+        }
+    }
+}
 
+// 一次清理的结果：按目标统计删除的项数和字节数，以及各文件系统可用空间的变化；
+// 压缩的日志同时计入所属目标，节省的空间另外汇总；移入隔离区的项计入项数，但不算作释放的空间
+struct CleanSummary {
+    targets: Vec<TargetSummary>,
+    filesystems: Vec<FilesystemSpace>,
+    compressed: usize,
+    saved: u64,
+}
+
+struct TargetSummary {
+    name: String,
+    count: usize,
+    bytes: u64,
+    quarantined: u64,
+}
+
+impl CleanSummary {
+    // 记录清理前各文件系统的可用空间。扫描默认不跨越文件系统边界（scan.one_file_system），
+    // 只按各项所属的扫描根目录查找文件系统，不逐项查询；容器引擎对象的路径在套接字之下，不对应实际的文件系统
+    fn begin(plan: &[PlanEntry]) -> Self {
+        let mut roots: Vec<&Path> =
+            plan.iter().filter(|entry| entry.action != Action::Prune).map(|entry| entry.root.as_path()).collect();
+        roots.sort_unstable();
+        roots.dedup();
+        Self {
+            targets: Vec::new(),
+            filesystems: FilesystemSpace::snapshot(roots.into_iter()),
+            compressed: 0,
+            saved: 0,
+        }
+    }
+
+    fn record_compressed(&mut self, target: &str, saved: u64) {
+        self.record(target, saved);
+        self.compressed += 1;
+        self.saved += saved;
+    }
+
+    fn record(&mut self, target: &str, bytes: u64) {
+        let summary = self.target(target);
+        summary.count += 1;
+        summary.bytes += bytes;
+    }
+
+    fn record_quarantined(&mut self, target: &str, bytes: u64) {
+        let summary = self.target(target);
+        summary.count += 1;
+        summary.quarantined += bytes;
+    }
+
+    fn target(&mut self, target: &str) -> &mut TargetSummary {
+        match self.targets.iter().position(|summary| summary.name == target) {
+            Some(index) => &mut self.targets[index],
+            None => {
+                self.targets.push(TargetSummary { name: target.to_string(), count: 0, bytes: 0, quarantined: 0 });
+                self.targets.last_mut().expect("刚刚添加")
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        FilesystemSpace::refresh(&mut self.filesystems);
+    }
+
+    fn count(&self) -> usize {
+        self.targets.iter().map(|summary| summary.count).sum()
+    }
+
+    fn bytes(&self) -> u64 {
+        self.targets.iter().map(|summary| summary.bytes).sum()
+    }
+
+    fn quarantined(&self) -> u64 {
+        self.targets.iter().map(|summary| summary.quarantined).sum()
+    }
+}
+
+// 状态栏消息的类别，决定显示颜色
+#[derive(Clone, Copy, PartialEq)]
+enum Status {
+    Info,
+    Hint,
+    Running(Phase),
+    MemoryReleased,
+    Cleaned,
+    Previewed,
+}
 
 struct App {
-    cleaned_files: Vec<String>,
+    events: Vec<Event>,
     is_cleaning: Arc<AtomicBool>,
-    messages: Vec<String>,
+    messages: Vec<(Status, String)>,
     cleaning_finished: bool,
     is_releasing_memory: bool,
     memory_released_count: usize,
+    memory_freed: u64,
     files_cleaned_count: usize,
+    bytes_freed: u64,
+    dry_run: bool,
+    targets: Vec<Target>,
+    config: Arc<Config>,
+    platform: &'static dyn Platform,
 }
 
 impl App {
-    fn new() -> Self {
-        let mut messages = vec!["按 'q' 退出".to_string()];
-        if !is_admin::is_admin() {
-            messages.push("提示: 未以管理员权限运行, 可能部分文件无法清理或释放内存。".to_string());
+    fn new(dry_run: bool, targets: Vec<Target>, config: Arc<Config>, platform: &'static dyn Platform) -> Self {
+        let mut messages = vec![(Status::Info, "按 'q' 退出".to_string())];
+        if dry_run {
+            messages.push((Status::Hint, "提示: 预览模式, 只生成清理计划, 不会删除任何文件。".to_string()));
+        }
+        if !platform.is_admin() {
+            messages.push((Status::Hint, "提示: 未以管理员权限运行, 可能部分文件无法清理或释放内存。".to_string()));
         }
         Self {
-            cleaned_files: Vec::new(),
+            events: Vec::new(),
             is_cleaning: Arc::new(AtomicBool::new(false)),
             messages,
             cleaning_finished: false,
             is_releasing_memory: false,
             memory_released_count: 0,
+            memory_freed: 0,
             files_cleaned_count: 0,
+            bytes_freed: 0,
+            dry_run,
+            targets,
+            config,
+            platform,
         }
     }
 
-    fn start_release_memory(&mut self, sender: mpsc::Sender<String>) {
+    fn start_release_memory(&mut self, sender: EventSender) {
         self.is_releasing_memory = true;
-        self.messages.push("正在释放内存...".to_string());
-        let sender_clone = sender.clone();
+        self.messages.push((Status::Running(Phase::ReleaseMemory), Event::PhaseStarted(Phase::ReleaseMemory).to_string()));
+        let config = self.config.clone();
+        let platform = self.platform;
         tokio::spawn(async move {
-            let released_count = release_memory();
-            sender_clone.send(format!("MEMORY_RELEASE_COMPLETE:{}", released_count)).unwrap();
+            release_memory(platform, &config.memory, sender);
         });
     }
 
-    fn start_cleaning(&mut self, sender: mpsc::Sender<String>) {
+    fn start_cleaning(&mut self, sender: EventSender) {
         self.is_cleaning.store(true, Ordering::SeqCst);
         self.cleaning_finished = false;
-        self.messages.retain(|m| !m.starts_with("清理完成"));
-        self.messages.push("正在清理中...".to_string());
+        self.messages.retain(|(status, _)| *status != Status::Cleaned);
+        self.messages.push((Status::Running(Phase::Clean), Event::PhaseStarted(Phase::Clean).to_string()));
 
-        let paths = get_cached_paths();
+        let targets = self.targets.clone();
+        let config = self.config.clone();
+        let dry_run = self.dry_run;
+        let platform = self.platform;
         let is_cleaning_clone = self.is_cleaning.clone();
         let sender_clone = sender.clone();
 
         // 显示将要清理的文件夹
-        for path in &paths {
-            if let Some(folder_name) = path.file_name().and_then(|name| name.to_str()) {
+        for target in &targets {
+            if let Some(folder_name) = target.path.file_name().and_then(|name| name.to_str()) {
                 if folder_name.eq_ignore_ascii_case("downloads") {
-                    sender_clone.send("正在扫描下载文件夹 (仅清理临时文件)...".to_string()).ok();
+                    sender_clone.send(Event::Progress("正在扫描下载文件夹 (仅清理临时文件)...".to_string())).ok();
                 } else {
-                    sender_clone.send(format!("正在扫描: {}", path.display())).ok();
+                    sender_clone.send(Event::Progress(format!("正在扫描: {}", target.path.display()))).ok();
                 }
             }
         }
 
         tokio::spawn(async move {
-            let total_cleaned = paths
-                .par_iter()
-                .map(|path| clean_directory(path, sender.clone()))
-                .sum::<usize>();
-
-            // 扫描并清理磁盘上的.log文件
-            let log_files_cleaned = if LOG_SCAN_ENABLED {
-                scan_and_clean_log_files(sender.clone())
-            } else {
-                0
-            };
-
-            // 清空回收站
-            let recycle_bin_cleaned = empty_recycle_bin(sender.clone());
-            let recycle_count = if recycle_bin_cleaned { 1 } else { 0 };
-            let final_total = total_cleaned + log_files_cleaned + recycle_count;
+            // 先按相同的规则生成完整计划，再决定是否执行
+            let log_scan = config.log_scan.enabled.then_some(&config.log_scan);
+            let plan = build_plan(&targets, log_scan, &config.scan, sender.clone());
+
+            if dry_run {
+                report_plan(&plan, sender.clone());
+                sender.send(Event::Progress("预览: 将清空回收站 (未执行)".to_string())).ok();
+                is_cleaning_clone.store(false, Ordering::SeqCst);
+                return;
+            }
 
-            sender.send(format!("CLEANING_COMPLETE:{}", final_total)).unwrap();
+            run_clean(&plan, true, &config, platform, sender);
             is_cleaning_clone.store(false, Ordering::SeqCst);
         });
     }
+
+    fn handle_event(&mut self, event: Event, sender: &EventSender) {
+        match event {
+            Event::PhaseFinished { phase: Phase::ReleaseMemory, count, bytes } => {
+                self.memory_released_count = count;
+                self.memory_freed = bytes;
+                self.is_releasing_memory = false;
+                self.messages.retain(|(status, _)| *status != Status::Running(Phase::ReleaseMemory));
+                self.messages.push((Status::MemoryReleased, event.to_string()));
+                self.events.push(event);
+                self.start_cleaning(sender.clone());
+            }
+            Event::PhaseFinished { phase: Phase::Clean, count, bytes } => {
+                self.files_cleaned_count = count;
+                self.bytes_freed = bytes;
+                self.is_cleaning.store(false, Ordering::SeqCst);
+                self.cleaning_finished = true;
+                self.messages.retain(|(status, _)| *status != Status::Running(Phase::Clean));
+                self.messages.push((Status::Cleaned, event.to_string()));
+                self.events.push(event);
+
+                // 发送系统通知
+                send_completion_notification(self.platform, &self.config.notification, self.files_cleaned_count,
+                    self.bytes_freed, self.memory_released_count, self.memory_freed);
+            }
+            Event::PhaseFinished { phase: Phase::Scan, count, .. } if self.dry_run => {
+                self.files_cleaned_count = count;
+                self.cleaning_finished = true;
+                self.messages.retain(|(status, _)| *status != Status::Running(Phase::Clean));
+                self.messages.push((Status::Previewed,
+                    format!("预览完成! 共计划清理 {} 个文件/目录, 未删除任何内容。", count)));
+                self.events.push(event);
+            }
+            event => self.events.push(event),
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
+    let cli = cli::Cli::parse();
+    let platform = platform::current();
+    let mut config = match Config::load(cli.config.as_deref(), platform) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    if cli.quarantine {
+        config.quarantine.enabled = true;
+    }
+    let targets = match cli::select_targets(&cli, &config, platform) {
+        Ok(targets) => targets,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+
+    match cli.command {
+        Some(command) => {
+            if !cli::run(command, cli.dry_run, targets, &config, platform) {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        None => run_tui(cli.dry_run, targets, config, platform).await,
+    }
+}
+
+async fn run_tui(dry_run: bool, targets: Vec<Target>, config: Config, platform: &'static dyn Platform) -> io::Result<()> {
     let mut terminal = init_terminal()?;
     let (tx, rx) = mpsc::channel();
-    let mut app = App::new();
+    let mut app = App::new(dry_run, targets, Arc::new(config), platform);
 
     // 立即绘制初始界面框架，避免空白
     terminal.draw(|frame| {
         draw_ui(frame, &app);
     })?;
 
-    app.start_release_memory(tx.clone());
+    if dry_run {
+        app.start_cleaning(tx.clone());
+    } else {
+        app.start_release_memory(tx.clone());
+    }
 
     loop {
         terminal.draw(|frame| {
             draw_ui(frame, &app);
         })?;
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => break,
-                    _ => {}
-                }
-            }
+        if terminal_event::poll(Duration::from_millis(100))?
+            && let TerminalEvent::Key(key) = terminal_event::read()?
+            && key.code == KeyCode::Char('q')
+        {
+            break;
         }
 
-        if let Ok(msg) = rx.try_recv() {
-            if msg.starts_with("MEMORY_RELEASE_COMPLETE:") {
-                let count_str = msg.strip_prefix("MEMORY_RELEASE_COMPLETE:").unwrap_or("0");
-                app.memory_released_count = count_str.parse().unwrap_or(0);
-                app.is_releasing_memory = false;
-                app.messages.retain(|m| m != "正在释放内存...");
-                let display_msg = format!("内存释放完成! 共整理了 {} 个进程。", app.memory_released_count);
-                app.messages.push(display_msg.clone());
-                app.cleaned_files.push(display_msg);
-                app.start_cleaning(tx.clone());
-            } else if msg.starts_with("CLEANING_COMPLETE:") {
-                let count_str = msg.strip_prefix("CLEANING_COMPLETE:").unwrap_or("0");
-                app.files_cleaned_count = count_str.parse().unwrap_or(0);
-                app.is_cleaning.store(false, Ordering::SeqCst);
-                app.cleaning_finished = true;
-                app.messages.retain(|m| m != "正在清理中...");
-                let display_msg = format!("清理完成! 总共清理了 {} 个文件/目录。", app.files_cleaned_count);
-                app.messages.push(display_msg);
-
-                // 发送系统通知
-                send_completion_notification(app.files_cleaned_count, app.memory_released_count);
-            } else {
-                app.cleaned_files.push(msg);
-            }
+        if let Ok(event) = rx.try_recv() {
+            app.handle_event(event, &tx);
         }
     }
 
@@ -193,190 +525,274 @@ fn shutdown_terminal(mut terminal: Terminal<CrosstermBackend<Stdout>>) -> io::Re
     Ok(())
 }
 
-fn get_cached_paths() -> Vec<PathBuf> {
+// 内置目标（可在配置中关闭）加上配置文件中定义的目标
+fn get_cached_targets(config: &Config, platform: &dyn Platform) -> Vec<Target> {
+    let mut targets = if config.builtin_targets { builtin_targets(config, platform) } else { Vec::new() };
+    if config.builtin_targets && config.journal.enabled && cfg!(unix) {
+        targets.push(Target::new("journal", &config.journal.dir).with_journal(config.journal.clone()));
+    }
+    if config.builtin_targets && config.xdg_cache.enabled && cfg!(unix)
+        && let Some(cache_dir) = platform.known_folder(KnownFolder::Cache)
+    {
+        targets.push(Target::new("xdg-cache", cache_dir)
+            .with_rules(xdg::rules(&config.xdg_cache))
+            .with_category("应用缓存")
+            .with_breakdown());
+    }
+    if config.builtin_targets && config.dev_caches.enabled && cfg!(unix) {
+        targets.extend(
+            devcache::targets(&config.dev_caches, platform.known_folder(KnownFolder::Home).as_deref())
+                .into_iter()
+                .map(|(name, path, dev_cache)| Target::new(name, path).with_dev_cache(dev_cache)),
+        );
+    }
+    if config.builtin_targets && config.containers.enabled && cfg!(unix) {
+        targets.extend(
+            container::targets(&config.containers)
+                .into_iter()
+                .map(|(name, socket)| Target::new(name, socket).with_container(config.containers.clone())),
+        );
+    }
+    // 包管理器的缓存属于 root，需要管理员权限
+    if config.builtin_targets && config.package_caches.enabled && cfg!(unix) && platform.is_admin() {
+        targets.extend(
+            pkgcache::targets(&config.package_caches)
+                .into_iter()
+                .map(|(name, path, package_cache)| Target::new(name, path).with_package_cache(package_cache)),
+        );
+    }
+
+    // 没有 path 的条目按名称覆盖内置目标的设置，本机没有该内置目标时忽略
+    for target_config in config.targets.iter().filter(|target_config| target_config.path.is_none()) {
+        for target in targets.iter_mut().filter(|target| target.name == target_config.name) {
+            target.apply_config(target_config);
+        }
+    }
+    for target_config in &config.targets {
+        if let Some(path) = &target_config.path {
+            targets.push(Target::from_config(target_config, path));
+        }
+    }
+    targets
+}
+
+// 临时目录中的文件可能正被安装程序、编译器等使用，内置的临时目录目标默认只清理超过该天数未变化的项
+const TEMP_MIN_AGE_DAYS: u64 = 7;
+
+fn builtin_targets(config: &Config, platform: &dyn Platform) -> Vec<Target> {
     let mut paths = Vec::new();
 
     if cfg!(windows) {
         // 临时文件夹
-        if let Ok(temp) = env::var("TEMP") { paths.push(temp.into()); }
+        if let Ok(temp) = env::var("TEMP") { paths.push(Target::new("temp", temp).with_min_age(TEMP_MIN_AGE_DAYS)); }
         if let Ok(tmp) = env::var("TMP") {
             let path_buf = PathBuf::from(tmp);
-            if !paths.contains(&path_buf) { paths.push(path_buf); }
+            if !paths.iter().any(|target| target.path == path_buf) {
+                paths.push(Target::new("temp", path_buf).with_min_age(TEMP_MIN_AGE_DAYS));
+            }
         }
 
         // Windows系统级缓存
         if let Ok(win_dir) = env::var("windir") {
-            paths.push(Path::new(&win_dir).join("Prefetch"));
-            paths.push(Path::new(&win_dir).join("Logs"));
-            paths.push(Path::new(&win_dir).join("SoftwareDistribution").join("Download"));
-            paths.push(Path::new(&win_dir).join("Temp"));
+            paths.push(Target::new("prefetch", Path::new(&win_dir).join("Prefetch")));
+            paths.push(Target::new("windows-logs", Path::new(&win_dir).join("Logs")));
+            paths.push(Target::new("windows-update", Path::new(&win_dir).join("SoftwareDistribution").join("Download")));
+            paths.push(Target::new("windows-temp", Path::new(&win_dir).join("Temp")).with_min_age(TEMP_MIN_AGE_DAYS));
         }
 
         // 用户相关缓存和临时文件
-        if let Ok(user_profile) = env::var("USERPROFILE") {
-            let user_path = Path::new(&user_profile);
+        if let Some(user_profile) = platform.known_folder(KnownFolder::Home) {
+            let user_path = user_profile.as_path();
 
             // 下载文件夹（只清理特定类型的文件）
             let downloads_path = user_path.join("Downloads");
             if downloads_path.exists() {
-                paths.push(downloads_path);
+                paths.push(Target::new("downloads", downloads_path));
             }
 
             // 回收站
-            paths.push(user_path.join("AppData").join("Local").join("Microsoft").join("Windows").join("Explorer").join("ThumbCacheToDelete"));
+            paths.push(Target::new("thumbnails", user_path.join("AppData").join("Local").join("Microsoft").join("Windows").join("Explorer").join("ThumbCacheToDelete")));
 
             // 浏览器缓存
-            paths.push(user_path.join("AppData").join("Local").join("Microsoft").join("Edge").join("User Data").join("Default").join("Cache"));
-            paths.push(user_path.join("AppData").join("Local").join("Google").join("Chrome").join("User Data").join("Default").join("Cache"));
-            paths.push(user_path.join("AppData").join("Local").join("Mozilla").join("Firefox").join("Profiles"));
+            paths.push(Target::new("edge", user_path.join("AppData").join("Local").join("Microsoft").join("Edge").join("User Data").join("Default").join("Cache")));
+            paths.push(Target::new("chrome", user_path.join("AppData").join("Local").join("Google").join("Chrome").join("User Data").join("Default").join("Cache")));
+            paths.push(Target::new("firefox", user_path.join("AppData").join("Local").join("Mozilla").join("Firefox").join("Profiles")));
 
             // Windows Store缓存
-            paths.push(user_path.join("AppData").join("Local").join("Packages").join("Microsoft.WindowsStore_8wekyb3d8bbwe").join("LocalCache"));
+            paths.push(Target::new("store", user_path.join("AppData").join("Local").join("Packages").join("Microsoft.WindowsStore_8wekyb3d8bbwe").join("LocalCache")));
 
             // 系统错误报告
-            paths.push(user_path.join("AppData").join("Local").join("CrashDumps"));
+            paths.push(Target::new("crashdumps", user_path.join("AppData").join("Local").join("CrashDumps")));
 
             // 最近使用的文件缓存
-            paths.push(user_path.join("AppData").join("Roaming").join("Microsoft").join("Windows").join("Recent"));
+            paths.push(Target::new("recent", user_path.join("AppData").join("Roaming").join("Microsoft").join("Windows").join("Recent")));
         }
 
         // 系统级缓存（需要管理员权限）
-        if is_admin::is_admin() {
-            paths.push(PathBuf::from("C:\\Windows\\SoftwareDistribution\\Download"));
-            paths.push(PathBuf::from("C:\\ProgramData\\Microsoft\\Windows\\WER\\ReportQueue"));
-            paths.push(PathBuf::from("C:\\Windows\\LiveKernelReports"));
-            paths.push(PathBuf::from("C:\\Windows\\Minidump"));
+        if platform.is_admin() {
+            paths.push(Target::new("windows-update", "C:\\Windows\\SoftwareDistribution\\Download"));
+            paths.push(Target::new("error-reports", "C:\\ProgramData\\Microsoft\\Windows\\WER\\ReportQueue"));
+            paths.push(Target::new("kernel-reports", "C:\\Windows\\LiveKernelReports"));
+            paths.push(Target::new("minidump", "C:\\Windows\\Minidump"));
         }
     } else if cfg!(unix) {
-        paths.push(PathBuf::from("/var/log"));
-        paths.push(PathBuf::from("/tmp"));
+        paths.push(Target::new("var-log", "/var/log").with_rotated_logs(config.log_scan.clone()));
+        // X11 等会话使用的套接字目录需要保留
+        paths.push(Target::new("tmp", "/tmp")
+            .with_keep(&[".X11-unix", ".ICE-unix", ".XIM-unix", ".font-unix", ".Test-unix"])
+            .with_min_age(TEMP_MIN_AGE_DAYS));
+        // 各浏览器所有配置文件中的缓存目录
+        paths.extend(browser::targets(platform));
     }
 
     paths
 }
 
-fn clean_directory(dir: &Path, sender: mpsc::Sender<String>) -> usize {
-    if !dir.exists() {
-        return 0;
+fn plan_directory(target: &Target, mounts: &Mounts) -> Vec<PlanEntry> {
+    // 容器引擎目标的路径是 API 套接字，不遍历目录
+    if let TargetKind::Container(config) = &target.kind {
+        return container::plan(target, config);
     }
-
-    if let Ok(entries) = fs::read_dir(dir) {
-        let entries: Vec<_> = entries.filter_map(Result::ok).collect();
-        let dir_str = dir.to_string_lossy().to_lowercase();
-
-        return entries
-            .par_iter()
-            .map(|entry| {
-                let path = entry.path();
-                let mut count = 0;
-
-                if should_clean_file(&path, &dir_str) {
-                    if path.is_dir() {
-                        if fs::remove_dir_all(&path).is_ok() {
-                            sender.send(format!("已删除目录: {:?}", path)).ok();
-                            count += 1;
-                        }
-                    } else if path.is_file() {
-                        if fs::remove_file(&path).is_ok() {
-                            let file_type = get_file_type_description(&dir_str);
-                            sender.send(format!("已删除{}: {:?}", file_type, path)).ok();
-                            count += 1;
-                        }
-                    }
-                }
-                count
-            })
-            .sum();
+    let dir = target.path.as_path();
+    let Ok(metadata) = fs::metadata(dir) else {
+        return Vec::new();
+    };
+    if !metadata.is_dir() || !mounts.allows_root(dir) || target.is_locked() {
+        return Vec::new();
     }
-    0
-}
-
-fn should_clean_file(path: &Path, dir_str: &str) -> bool {
-    if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
-        let file_name_lower = file_name.to_lowercase();
-
-        // 下载文件夹特殊处理
-        if dir_str.contains("downloads") {
-            return should_clean_download_file(path);
-        }
-
-        // Firefox配置文件夹特殊处理
-        if dir_str.contains("firefox") && dir_str.contains("profiles") {
-            return file_name_lower.contains("cache") ||
-                   file_name_lower.contains("temp") ||
-                   file_name_lower.ends_with(".tmp");
-        }
 
-        // Recent文件夹特殊处理
-        if dir_str.contains("recent") {
-            return file_name_lower.ends_with(".lnk");
+    let resolved;
+    let target = match &target.kind {
+        TargetKind::Directory => target,
+        TargetKind::DevCache(dev_cache) => {
+            resolved = Target { rules: dev_cache.rules(), ..target.clone() };
+            &resolved
         }
-
-        // 浏览器缓存文件夹
-        if dir_str.contains("cache") || dir_str.contains("temp") {
-            return true;
+        TargetKind::PackageCache(package_cache) => {
+            resolved = Target { rules: package_cache.rules(), ..target.clone() };
+            &resolved
         }
-
-        // 错误报告和转储文件
-        if dir_str.contains("crashdumps") ||
-           dir_str.contains("reportqueue") ||
-           dir_str.contains("minidump") ||
-           dir_str.contains("livekernelreports") {
-            return true;
+        TargetKind::Journal(config) => return journal::plan(target, config),
+        TargetKind::RotatedLogs(log_scan) => {
+            // 规则只匹配轮转出来的文件，正在写入的日志不会进入计划，所以不需要检查打开的文件
+            let dir_str = dir.to_string_lossy().to_lowercase();
+            let (candidates, _) = plan_tree(target, mounts, dir, &metadata, &dir_str, None);
+            let files = candidates.into_iter().filter(|entry| !entry.is_dir).collect();
+            return rotation::plan(files, log_scan, &OpenFiles::default());
         }
+        TargetKind::Container(_) => unreachable!("容器引擎目标不遍历目录"),
+    };
 
-        // 默认临时文件清理
-        let temp_extensions = [
-            ".tmp", ".temp", ".cache", ".log", ".dmp", ".mdmp"
-        ];
-
-        for ext in &temp_extensions {
-            if file_name_lower.ends_with(ext) {
-                return true;
-            }
-        }
-    }
-
-    true // 对于其他系统临时文件夹，默认清理所有内容
+    let dir_str = dir.to_string_lossy().to_lowercase();
+    plan_tree(target, mounts, dir, &metadata, &dir_str, None).0
 }
 
-fn should_clean_download_file(path: &Path) -> bool {
-    if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
-        let file_name_lower = file_name.to_lowercase();
+// 目录中的一项在计划中的结果
+enum PlannedChild {
+    File(PlanEntry),
+    Dir(Vec<PlanEntry>, bool),
+    Kept,
+}
 
-        // 只清理明确的临时文件和缓存文件
-        let temp_extensions = [
-            ".tmp", ".temp", ".cache", ".crdownload", ".part",
-            ".partial", ".download", ".!ut", ".bc!", ".crx"
-        ];
+// 递归遍历目标目录，对每一层的文件应用规则和保留策略；inherited 为命中上级目录的规则。
+// 返回的布尔值表示目录中的内容是否全部计划删除，这时目录本身也可以在清空后删除
+fn plan_tree(
+    target: &Target,
+    mounts: &Mounts,
+    dir: &Path,
+    dir_metadata: &fs::Metadata,
+    dir_str: &str,
+    inherited: Option<&Rule>,
+) -> (Vec<PlanEntry>, bool) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (Vec::new(), false);
+    };
+    let entries: Vec<_> = entries.filter_map(Result::ok).collect();
+    let age_by = target.retention.age_by;
 
-        let temp_prefixes = [
-            "~", "tmp", "temp", "cache"
-        ];
+    let children: Vec<PlannedChild> = entries
+        .par_iter()
+        .map(|entry| {
+            let path = entry.path();
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                return PlannedChild::Kept;
+            };
+            let relative = path.strip_prefix(&target.path).unwrap_or(&path);
+            let rule = match target.rules.check(relative, metadata.is_dir()) {
+                Verdict::Excluded => return PlannedChild::Kept,
+                Verdict::Included(rule) => Some(rule),
+                Verdict::Unmatched => inherited,
+            };
 
-        // 检查文件扩展名
-        for ext in &temp_extensions {
-            if file_name_lower.ends_with(ext) {
-                return true;
+            // 其他文件系统和挂载点按配置决定是否进入，挂载点本身永远不删除
+            let mount_point = metadata.is_dir() && mounts.is_boundary(dir_metadata, &path, &metadata);
+            if mount_point && !mounts.allows_dir(dir_metadata, &path, &metadata) {
+                return PlannedChild::Kept;
             }
-        }
 
-        // 检查文件名前缀
-        for prefix in &temp_prefixes {
-            if file_name_lower.starts_with(prefix) {
-                return true;
+            if metadata.is_dir() {
+                let (mut plan, emptied) = plan_tree(target, mounts, &path, &metadata, dir_str, rule);
+                let time = dir_time(&metadata, age_by);
+                let rule = rule
+                    .filter(|rule| rule.allows(0, time, true))
+                    .filter(|_| emptied && !mount_point && !target.is_kept(&path))
+                    .filter(|_| is_old_enough(time, &target.retention));
+                if let Some(rule) = rule {
+                    plan.push(PlanEntry {
+                        target: target.name.clone(),
+                        root: target.path.clone(),
+                        size: 0,
+                        path,
+                        is_dir: true,
+                        rule: rule.name().to_string(),
+                        category: "空目录",
+                        time,
+                        action: Action::Delete,
+                    });
+                }
+                PlannedChild::Dir(plan, rule.is_some())
+            } else if metadata.is_file() || metadata.is_symlink() {
+                let time = entry_time(&metadata, age_by);
+                match rule.filter(|rule| rule.allows(metadata.len(), time, false)) {
+                    Some(rule) => PlannedChild::File(PlanEntry {
+                        target: target.name.clone(),
+                        root: target.path.clone(),
+                        size: metadata.len(),
+                        path,
+                        is_dir: false,
+                        rule: rule.name().to_string(),
+                        category: target.category.unwrap_or_else(|| get_file_type_description(dir_str)),
+                        time,
+                        action: Action::Delete,
+                    }),
+                    None => PlannedChild::Kept,
+                }
+            } else {
+                PlannedChild::Kept
             }
-        }
-
-        // 检查是否是浏览器临时文件
-        if file_name_lower.contains("temp") ||
-           file_name_lower.contains("cache") ||
-           file_name_lower.contains("tmp") {
-            return true;
+        })
+        .collect();
+
+    let mut plan = Vec::new();
+    let mut files = Vec::new();
+    let mut emptied = true;
+    for child in children {
+        match child {
+            PlannedChild::File(entry) => files.push(entry),
+            PlannedChild::Dir(subtree, removed) => {
+                plan.extend(subtree);
+                emptied &= removed;
+            }
+            PlannedChild::Kept => emptied = false,
         }
     }
 
-    false
+    // 保留策略按目录分别应用，keep_newest 表示每个目录中保留最新的 N 个文件
+    let candidates = files.len();
+    let files = apply_retention(files, &target.retention);
+    emptied &= files.len() == candidates;
+    plan.extend(files);
+    (plan, emptied)
 }
 
 fn get_file_type_description(dir_str: &str) -> &'static str {
@@ -401,115 +817,405 @@ fn get_file_type_description(dir_str: &str) -> &'static str {
     }
 }
 
-fn release_memory() -> usize {
-    let process_ids = unsafe {
-        let mut pids = Vec::with_capacity(1024);
-        let mut cb_needed = 0;
-        if EnumProcesses(pids.as_mut_ptr(), (pids.capacity() * std::mem::size_of::<u32>()) as u32, &mut cb_needed) != 0 {
-            pids.set_len((cb_needed / std::mem::size_of::<u32>() as u32) as usize);
-            pids
-        } else {
-            Vec::new()
+// 扫描所有目标（以及可选的磁盘日志文件），生成去重后的清理计划
+fn build_plan(targets: &[Target], log_scan: Option<&LogScanConfig>, scan: &ScanConfig, sender: EventSender) -> Vec<PlanEntry> {
+    sender.send(Event::PhaseStarted(Phase::Scan)).ok();
+    let mounts = Mounts::load(scan);
+    for target in targets.iter().filter(|target| target.is_locked()) {
+        if let Some(lock) = &target.lock {
+            sender.send(Event::Skipped { path: target.path.clone(), reason: SkipReason::Locked(lock.clone()) }).ok();
         }
+    }
+    let mut plan: Vec<PlanEntry> = targets
+        .par_iter()
+        .flat_map_iter(|target| plan_directory(target, &mounts))
+        .collect();
+
+    // 扫描磁盘上的.log文件
+    let open_files = OpenFiles::collect();
+    if let Some(log_scan) = log_scan {
+        plan.extend(plan_log_files(log_scan, &mounts, &open_files, sender.clone()));
+    }
+    let plan = protect_plan(dedup_plan(plan), &open_files, &sender);
+
+    let bytes = plan.iter().map(|entry| entry.size).sum();
+    sender.send(Event::PhaseFinished { phase: Phase::Scan, count: plan.len(), bytes }).ok();
+    plan
+}
+
+fn entry_time(metadata: &fs::Metadata, age_by: AgeBy) -> Option<SystemTime> {
+    match age_by {
+        AgeBy::Mtime => metadata.modified().ok(),
+        AgeBy::Atime => metadata.accessed().ok(),
+        AgeBy::Ctime => changed_time(metadata),
+    }
+}
+
+// 扫描本身会更新目录的访问时间，所以按 atime 计算时目录改用修改时间
+fn dir_time(metadata: &fs::Metadata, age_by: AgeBy) -> Option<SystemTime> {
+    entry_time(metadata, if age_by == AgeBy::Atime { AgeBy::Mtime } else { age_by })
+}
+
+#[cfg(unix)]
+fn changed_time(metadata: &fs::Metadata) -> Option<SystemTime> {
+    use std::os::unix::fs::MetadataExt;
+    let secs = u64::try_from(metadata.ctime()).ok()?;
+    std::time::UNIX_EPOCH.checked_add(Duration::new(secs, metadata.ctime_nsec() as u32))
+}
+
+#[cfg(not(unix))]
+fn changed_time(metadata: &fs::Metadata) -> Option<SystemTime> {
+    metadata.created().ok()
+}
+
+// 按保留策略筛选：先留下最新的 N 项，再去掉太新或太小的项；时间未知的项视为最旧
+fn apply_retention(mut plan: Vec<PlanEntry>, retention: &Retention) -> Vec<PlanEntry> {
+    if retention.keep_newest > 0 {
+        plan.sort_by_key(|entry| std::cmp::Reverse(entry.time));
+        plan.drain(..retention.keep_newest.min(plan.len()));
+    }
+
+    plan.into_iter()
+        .filter(|entry| entry.size >= retention.min_size)
+        .filter(|entry| is_old_enough(entry.time, retention))
+        .collect()
+}
+
+fn is_old_enough(time: Option<SystemTime>, retention: &Retention) -> bool {
+    let min_age = Duration::from_secs(retention.min_age_days * 24 * 3600);
+    retention.min_age_days == 0
+        || time.and_then(|time| SystemTime::now().duration_since(time).ok()).is_some_and(|age| age >= min_age)
+}
+
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+// 目录中所有文件的大小之和，不跟随符号链接
+fn tree_size(path: &Path) -> u64 {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return 0;
     };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .filter_map(Result::ok)
+        .map(|entry| tree_size(&entry.path()))
+        .sum()
+}
 
-    process_ids.par_iter().filter_map(|&pid| {
-        let process_handle = unsafe {
-            OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ, 0, pid)
-        };
+// 去掉重复项（日志扫描与各目标、以及相互嵌套的目标可能重叠）
+fn dedup_plan(plan: Vec<PlanEntry>) -> Vec<PlanEntry> {
+    let mut seen = HashSet::new();
+    plan.into_iter()
+        .filter(|entry| seen.insert(entry.path.clone()))
+        .collect()
+}
+
+// 去掉正在被进程使用或包含特殊文件的项
+fn protect_plan(plan: Vec<PlanEntry>, open_files: &OpenFiles, sender: &EventSender) -> Vec<PlanEntry> {
+    plan.into_par_iter()
+        .filter(|entry| match check_entry(entry, open_files) {
+            Some(reason) => {
+                sender.send(Event::Skipped { path: entry.path.clone(), reason }).ok();
+                false
+            }
+            None => true,
+        })
+        .collect()
+}
+
+// 截断的正是仍在写入的日志，只需排除特殊文件
+fn check_entry(entry: &PlanEntry, open_files: &OpenFiles) -> Option<SkipReason> {
+    match open_files.check(&entry.path) {
+        Some(SkipReason::InUse(_)) if entry.action == Action::Truncate => None,
+        reason => reason,
+    }
+}
+
+fn report_plan(plan: &[PlanEntry], sender: EventSender) {
+    for entry in plan {
+        sender.send(Event::Planned {
+            path: entry.path.clone(),
+            action: entry.action,
+            category: entry.category,
+            rule: entry.rule.clone(),
+            bytes: entry.size,
+        }).ok();
+    }
+    report_target_totals(plan, &sender);
+
+    let total_size: u64 = plan.iter().map(|entry| entry.size).sum();
+    sender.send(Event::Progress(format!("预览: 共 {} 项, 预计可释放 {}", plan.len(), format_size(total_size)))).ok();
+}
+
+// 按目标汇总计划中的项数和大小
+fn report_target_totals(plan: &[PlanEntry], sender: &EventSender) {
+    let mut target_names: Vec<&str> = Vec::new();
+    for entry in plan {
+        if !target_names.contains(&entry.target.as_str()) {
+            target_names.push(&entry.target);
+        }
+    }
+    for name in target_names {
+        let (count, size) = plan.iter()
+            .filter(|entry| entry.target == name)
+            .fold((0, 0), |(count, size), entry| (count + 1, size + entry.size));
+        sender.send(Event::TargetTotal { target: name.to_string(), count, bytes: size, quarantined: 0, dry_run: true }).ok();
+    }
+}
+
+// 执行清理计划并汇总结果；启用隔离时先把各项移入隔离区，且不清空回收站
+fn run_clean(plan: &[PlanEntry], empty_recycle: bool, config: &Config, platform: &dyn Platform, sender: EventSender) -> CleanSummary {
+    let quarantine_config = &config.quarantine;
+    sender.send(Event::PhaseStarted(Phase::Clean)).ok();
+    quarantine::expire(quarantine_config, sender.clone());
+
+    let quarantine = if quarantine_config.enabled {
+        match Quarantine::begin(quarantine_config) {
+            Ok(quarantine) => Some(quarantine),
+            Err(error) => {
+                sender.send(Event::QuarantineFailed { path: quarantine_config.dir.clone(), error }).ok();
+                sender.send(Event::PhaseFinished { phase: Phase::Clean, count: 0, bytes: 0 }).ok();
+                return CleanSummary::begin(&[]);
+            }
+        }
+    } else {
+        None
+    };
+
+    // 扫描到执行之间文件可能被重新打开，执行前再检查一次
+    let open_files = OpenFiles::collect();
+    let mut summary = CleanSummary::begin(plan);
+    execute_plan(plan, &open_files, quarantine.as_ref(), &mut summary, sender.clone());
+
+    if let Some(quarantine) = &quarantine {
+        sender.send(Event::Progress(format!("已移入隔离区 {} ({}), {} 天后自动删除, 可使用 restore {} 恢复",
+            quarantine.run_id(), format_size(summary.quarantined()), quarantine_config.expire_days, quarantine.run_id()))).ok();
+    } else if empty_recycle {
+        // 清空回收站
+        if let Some(bytes) = empty_recycle_bin(platform, config, sender.clone()) {
+            summary.record("recycle-bin", bytes);
+        }
+    }
+    summary.finish();
+    report_summary(&summary, sender.clone());
+
+    sender.send(Event::PhaseFinished { phase: Phase::Clean, count: summary.count(), bytes: summary.bytes() }).ok();
+    summary
+}
 
-        if !process_handle.is_null() {
-            let mut counters = std::mem::MaybeUninit::<PROCESS_MEMORY_COUNTERS>::uninit();
-            let result = unsafe {
-                GetProcessMemoryInfo(process_handle, counters.as_mut_ptr(), std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32)
+fn report_summary(summary: &CleanSummary, sender: EventSender) {
+    for target in &summary.targets {
+        sender.send(Event::TargetTotal {
+            target: target.name.clone(),
+            count: target.count,
+            bytes: target.bytes,
+            quarantined: target.quarantined,
+            dry_run: false,
+        }).ok();
+    }
+    if summary.compressed > 0 {
+        sender.send(Event::CompressionTotal { count: summary.compressed, saved: summary.saved }).ok();
+    }
+
+    for fs in &summary.filesystems {
+        if let (Some(before), Some(after)) = (fs.before, fs.after) {
+            sender.send(Event::FilesystemSpace { root: fs.root.clone(), before, after }).ok();
+        }
+    }
+}
+
+// 先并行处理文件，再由深到浅删除已清空的目录
+fn execute_plan(plan: &[PlanEntry], open_files: &OpenFiles, quarantine: Option<&Quarantine>, summary: &mut CleanSummary, sender: EventSender) {
+    let (mut dirs, files): (Vec<&PlanEntry>, Vec<&PlanEntry>) = plan.iter().partition(|entry| entry.is_dir);
+
+    let processed: Vec<(&PlanEntry, u64)> = files.into_par_iter()
+        .filter_map(|entry| execute_file(entry, open_files, quarantine, &sender).map(|bytes| (entry, bytes)))
+        .collect();
+
+    for (entry, bytes) in processed {
+        match entry.action {
+            Action::Compress(_) => summary.record_compressed(&entry.target, bytes),
+            Action::Delete if quarantine.is_some() => summary.record_quarantined(&entry.target, bytes),
+            _ => summary.record(&entry.target, bytes),
+        }
+    }
+
+    // 空目录不放入隔离区，恢复时会重新创建上级目录；
+    // 目录中有文件被跳过或删除失败时目录不为空，这时静默保留
+    dirs.sort_by_key(|entry| std::cmp::Reverse(entry.path.components().count()));
+    for entry in dirs {
+        if let Some(reason) = open_files.check(&entry.path) {
+            sender.send(Event::Skipped { path: entry.path.clone(), reason }).ok();
+            continue;
+        }
+        match rooted::remove_dir(&entry.root, &entry.path) {
+            Ok(()) => {
+                sender.send(Event::DirDeleted { path: entry.path.clone(), bytes: entry.size }).ok();
+                summary.record(&entry.target, entry.size);
+            }
+            Err(error) if matches!(error.kind(), io::ErrorKind::DirectoryNotEmpty | io::ErrorKind::NotFound) => {}
+            Err(error) => {
+                sender.send(Event::DeleteFailed { path: entry.path.clone(), error }).ok();
+            }
+        }
+    }
+}
+
+// 处理计划中的一个文件，成功时返回释放的字节数（压缩时为压缩前后的差，隔离时为移入隔离区的大小）
+fn execute_file(entry: &PlanEntry, open_files: &OpenFiles, quarantine: Option<&Quarantine>, sender: &EventSender) -> Option<u64> {
+    if let Some(reason) = check_entry(entry, open_files) {
+        sender.send(Event::Skipped { path: entry.path.clone(), reason }).ok();
+        return None;
+    }
+
+    match entry.action {
+        // 截断的内容和容器引擎的对象无法放入隔离区；压缩不丢失内容，照常执行
+        Action::Truncate | Action::Prune if quarantine.is_some() => {
+            sender.send(Event::Skipped { path: entry.path.clone(), reason: SkipReason::Irreversible }).ok();
+            None
+        }
+        Action::Truncate => match rooted::truncate(&entry.root, &entry.path) {
+            Ok(bytes) => {
+                sender.send(Event::Truncated { path: entry.path.clone(), bytes }).ok();
+                Some(bytes)
+            }
+            Err(error) => {
+                sender.send(Event::ActionFailed { path: entry.path.clone(), action: entry.action, error }).ok();
+                None
+            }
+        },
+        Action::Prune => match container::remove(&entry.root, &entry.path) {
+            Ok(()) => {
+                sender.send(Event::FileDeleted { path: entry.path.clone(), category: entry.category, bytes: entry.size }).ok();
+                Some(entry.size)
+            }
+            Err(error) => {
+                sender.send(Event::ActionFailed { path: entry.path.clone(), action: entry.action, error }).ok();
+                None
+            }
+        },
+        Action::Compress(format) => match compress::compress(&entry.root, &entry.path, format) {
+            Ok(compressed) => {
+                sender.send(Event::Compressed { path: entry.path.clone(), bytes: entry.size, compressed }).ok();
+                Some(entry.size.saturating_sub(compressed))
+            }
+            Err(error) => {
+                sender.send(Event::ActionFailed { path: entry.path.clone(), action: entry.action, error }).ok();
+                None
+            }
+        },
+        Action::Delete => {
+            let result = match quarantine {
+                Some(quarantine) => quarantine.hold(entry),
+                None => rooted::remove_file(&entry.root, &entry.path),
             };
 
-            if result != 0 {
-                unsafe {
-                    if EmptyWorkingSet(process_handle) != 0 {
-                        Some(1)
-                    } else {
-                        None
-                    }
+            match result {
+                Ok(()) if quarantine.is_some() => {
+                    sender.send(Event::Quarantined { path: entry.path.clone(), bytes: entry.size }).ok();
+                    Some(entry.size)
+                }
+                Ok(()) => {
+                    sender.send(Event::FileDeleted {
+                        path: entry.path.clone(),
+                        category: entry.category,
+                        bytes: entry.size,
+                    }).ok();
+                    Some(entry.size)
+                }
+                Err(error) => {
+                    sender.send(Event::DeleteFailed { path: entry.path.clone(), error }).ok();
+                    None
                 }
-            } else {
-                None
             }
-        } else {
-            None
         }
-    }).count()
+    }
 }
 
+// 释放内存并发送阶段结束事件，count 为整理的进程数，bytes 为测量到的释放量
+fn release_memory(platform: &dyn Platform, config: &MemoryConfig, sender: EventSender) {
+    let (count, bytes) = platform.trim_memory(config, &sender);
+    sender.send(Event::PhaseFinished { phase: Phase::ReleaseMemory, count, bytes }).ok();
+}
 
-
-fn send_completion_notification(cleaned_count: usize, memory_count: usize) {
-    if !NOTIFICATION_ENABLED {
+fn send_completion_notification(platform: &'static dyn Platform, notification: &NotificationConfig, cleaned_count: usize,
+    bytes_freed: u64, memory_count: usize, memory_freed: u64) {
+    if !notification.enabled {
         return; // 如果禁用了通知，直接返回
     }
 
     // 在后台线程中发送通知，避免阻塞主界面
-    let timeout = NOTIFICATION_TIMEOUT;
+    let timeout = notification.timeout_ms;
     tokio::spawn(async move {
         let title = "磁盘清理完成";
+        let memory = if memory_freed > 0 {
+            format!("释放了 {} 内存", format_size(memory_freed))
+        } else {
+            format!("优化了 {} 个进程内存", memory_count)
+        };
         let body = if cleaned_count > 0 {
-            format!("清理完成！清理了 {} 个文件/目录，优化了 {} 个进程内存", cleaned_count, memory_count)
+            format!("清理完成！清理了 {} 个文件/目录，释放了 {}，{}", cleaned_count, format_size(bytes_freed), memory)
         } else {
-            format!("清理完成！优化了 {} 个进程内存，系统已经很干净了", memory_count)
+            format!("清理完成！{}，系统已经很干净了", memory)
         };
 
-        // 尝试发送系统通知
-        match Notification::new()
-            .summary(title)
-            .body(&body)
-            .timeout(timeout as i32) // 使用配置的超时时间
-            .show()
-        {
-            Ok(_) => {
-                // 通知发送成功
-            }
-            Err(_) => {
-                // 通知发送失败，静默处理
-                // 在Windows上，如果没有合适的通知系统，这是正常的
-            }
-        }
+        // 尝试发送系统通知，没有可用的通知服务时静默忽略
+        platform.notify(title, &body, timeout).ok();
     });
 }
 
-fn scan_and_clean_log_files(sender: mpsc::Sender<String>) -> usize {
-    if !LOG_SCAN_ENABLED {
-        return 0;
-    }
-
-    sender.send("正在扫描磁盘上的.log文件...".to_string()).ok();
+fn plan_log_files(log_scan: &LogScanConfig, mounts: &Mounts, open_files: &OpenFiles, sender: EventSender) -> Vec<PlanEntry> {
+    sender.send(Event::Progress("正在扫描磁盘上的.log文件...".to_string())).ok();
 
-    let mut total_cleaned = 0;
+    let mut plan = Vec::new();
+    let rules = RuleList::logs();
 
-    for drive in LOG_SCAN_DRIVES {
+    for drive in &log_scan.drives {
         let drive_path = PathBuf::from(drive);
         if !drive_path.exists() {
             continue;
         }
 
-        sender.send(format!("正在扫描驱动器: {}", drive)).ok();
+        sender.send(Event::Progress(format!("正在扫描驱动器: {}", drive))).ok();
 
         // 扫描常见的日志文件位置
         let log_paths = get_common_log_paths(drive);
 
         for log_path in log_paths {
-            if log_path.exists() {
-                total_cleaned += scan_directory_for_logs(&log_path, sender.clone());
+            if let Ok(metadata) = fs::metadata(&log_path)
+                && metadata.is_dir()
+                && mounts.allows_root(&log_path)
+            {
+                scan_directory_for_logs(&log_path, &log_path, &metadata, &rules, mounts, &mut plan);
             }
         }
     }
 
-    if total_cleaned > 0 {
-        sender.send(format!("日志文件清理完成，共清理了 {} 个日志文件", total_cleaned)).ok();
+    let plan = rotation::plan(plan, log_scan, open_files);
+
+    if plan.is_empty() {
+        sender.send(Event::Progress("未找到需要清理的日志文件".to_string())).ok();
     } else {
-        sender.send("未找到需要清理的日志文件".to_string()).ok();
+        sender.send(Event::Progress(format!("日志文件扫描完成，共找到 {} 个需要清理的日志文件", plan.len()))).ok();
     }
 
-    total_cleaned
+    plan
 }
 
 fn get_common_log_paths(drive: &str) -> Vec<PathBuf> {
@@ -546,93 +1252,63 @@ fn get_common_log_paths(drive: &str) -> Vec<PathBuf> {
     paths
 }
 
-fn scan_directory_for_logs(dir: &Path, sender: mpsc::Sender<String>) -> usize {
-    let mut cleaned_count = 0;
-
+// 符号链接既不跟随也不删除
+fn scan_directory_for_logs(
+    root: &Path,
+    dir: &Path,
+    dir_metadata: &fs::Metadata,
+    rules: &RuleList,
+    mounts: &Mounts,
+    plan: &mut Vec<PlanEntry>,
+) {
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.filter_map(Result::ok) {
             let path = entry.path();
+            let Ok(metadata) = fs::symlink_metadata(&path) else {
+                continue;
+            };
 
-            if path.is_file() {
-                if should_clean_log_file(&path) {
-                    if let Ok(metadata) = fs::metadata(&path) {
-                        // 检查文件大小
-                        let file_size_mb = metadata.len() / (1024 * 1024);
-                        if file_size_mb >= LOG_MIN_SIZE_MB {
-                            // 检查文件年龄
-                            if let Ok(modified) = metadata.modified() {
-                                if let Ok(elapsed) = modified.elapsed() {
-                                    let file_age_days = elapsed.as_secs() / (24 * 3600);
-
-                                    if file_age_days >= LOG_MAX_AGE_DAYS {
-                                        if fs::remove_file(&path).is_ok() {
-                                            sender.send(format!("已删除日志文件: {} ({:.1}MB)",
-                                                path.display(), file_size_mb as f64)).ok();
-                                            cleaned_count += 1;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
+            if metadata.is_file() {
+                let file_name = entry.file_name();
+                if let Verdict::Included(rule) = rules.check(Path::new(&file_name), false) {
+                    // 年龄、大小和轮转代数由 rotation::plan 按轮转组检查
+                    plan.push(PlanEntry {
+                        target: "logs".to_string(),
+                        root: root.to_path_buf(),
+                        size: metadata.len(),
+                        path,
+                        is_dir: false,
+                        rule: rule.name().to_string(),
+                        category: "日志文件",
+                        time: metadata.modified().ok(),
+                        action: Action::Delete,
+                    });
                 }
-            } else if path.is_dir() {
-                // 递归扫描子目录，但限制深度避免无限递归
-                if let Some(dir_name) = path.file_name().and_then(|n| n.to_str()) {
-                    if !dir_name.starts_with('.') && dir_name != "System Volume Information" {
-                        cleaned_count += scan_directory_for_logs(&path, sender.clone());
-                    }
+            } else if metadata.is_dir() {
+                // 递归扫描子目录，但限制深度避免无限递归；不进入其他文件系统和挂载点
+                if let Some(dir_name) = path.file_name().and_then(|n| n.to_str())
+                    && !dir_name.starts_with('.')
+                    && dir_name != "System Volume Information"
+                    && mounts.allows_dir(dir_metadata, &path, &metadata)
+                {
+                    scan_directory_for_logs(root, &path, &metadata, rules, mounts, plan);
                 }
             }
         }
     }
-
-    cleaned_count
 }
 
-fn should_clean_log_file(path: &Path) -> bool {
-    if let Some(file_name) = path.file_name().and_then(|name| name.to_str()) {
-        let file_name_lower = file_name.to_lowercase();
-
-        // 检查是否是日志文件
-        if file_name_lower.ends_with(".log") ||
-           file_name_lower.ends_with(".log.old") ||
-           file_name_lower.ends_with(".log.1") ||
-           file_name_lower.ends_with(".log.2") ||
-           file_name_lower.ends_with(".log.3") ||
-           file_name_lower.ends_with(".log.4") ||
-           file_name_lower.ends_with(".log.5") {
-            return true;
+// 成功时返回释放的字节数（Windows 上为清空前回收站占用的大小）
+fn empty_recycle_bin(platform: &dyn Platform, config: &Config, sender: EventSender) -> Option<u64> {
+    sender.send(Event::PhaseStarted(Phase::RecycleBin)).ok();
+    match platform.empty_trash(config, &sender) {
+        Ok(bytes) => {
+            sender.send(Event::RecycleBinEmptied { bytes }).ok();
+            Some(bytes)
         }
-
-        // 检查其他日志文件格式
-        if file_name_lower.contains(".log.") ||
-           (file_name_lower.contains("log") && (
-               file_name_lower.ends_with(".txt") ||
-               file_name_lower.ends_with(".out") ||
-               file_name_lower.ends_with(".err")
-           )) {
-            return true;
-        }
-    }
-
-    false
-}
-
-fn empty_recycle_bin(sender: mpsc::Sender<String>) -> bool {
-    unsafe {
-        let result = SHEmptyRecycleBinW(
-            std::ptr::null_mut(), // 所有驱动器
-            std::ptr::null(),     // 清空所有文件
-            SHERB_NOCONFIRMATION | SHERB_NOPROGRESSUI | SHERB_NOSOUND, // 静默清空
-        );
-
-        if result == 0 {
-            sender.send("已清空回收站".to_string()).ok();
-            true
-        } else {
-            sender.send("清空回收站失败".to_string()).ok();
-            false
+        Err(error) => {
+            sender.send(Event::RecycleBinFailed(error)).ok();
+            None
         }
     }
 }
@@ -644,7 +1320,24 @@ fn draw_ui(frame: &mut ratatui::Frame, app: &App) {
         .constraints([ratatui::layout::Constraint::Percentage(80), ratatui::layout::Constraint::Percentage(20)])
         .split(frame.size());
 
-    let cleaned_list: Vec<ListItem> = app.cleaned_files.iter().map(|f| ListItem::new(f.as_str())).collect();
+    let cleaned_list: Vec<ListItem> = app.events.iter().map(|event| {
+        let item = ListItem::new(event.to_string());
+        match event {
+            Event::FileDeleted { .. } | Event::DirDeleted { .. } => item.style(Style::default().fg(Color::Red)),
+            Event::Compressed { .. } | Event::Truncated { .. } => item.style(Style::default().fg(Color::Green)),
+            Event::DeleteFailed { .. } | Event::ActionFailed { .. } | Event::RecycleBinFailed(_)
+                | Event::MemoryActionFailed { .. } => {
+                item.style(Style::default().fg(Color::Magenta))
+            }
+            Event::Planned { .. } => item.style(Style::default().fg(Color::Yellow)),
+            Event::Skipped { .. } => item.style(Style::default().fg(Color::DarkGray)),
+            Event::RecycleBinEmptied { .. } | Event::MemoryReclaimed { .. } | Event::MemoryCompacted { .. }
+                | Event::ProcessTrimmed { .. } => {
+                item.style(Style::default().fg(Color::Cyan))
+            }
+            _ => item,
+        }
+    }).collect();
     let cleaned_list_widget = List::new(cleaned_list)
         .block(Block::default()
             .title("操作日志")
@@ -653,19 +1346,12 @@ fn draw_ui(frame: &mut ratatui::Frame, app: &App) {
     frame.render_widget(cleaned_list_widget, main_layout[0]);
 
     let messages_widget = Paragraph::new(
-        app.messages.iter().map(|msg| {
-            let color = if msg.starts_with("已删除") {
-                Color::Red
-            } else if msg.starts_with("已清空回收站") {
-                Color::Cyan
-            } else if msg.starts_with("内存释放完成") {
-                Color::Green
-            } else if msg.starts_with("清理完成") {
-                Color::Yellow
-            } else if msg.starts_with("提示") {
-                Color::Magenta
-            } else {
-                Color::White
+        app.messages.iter().map(|(status, msg)| {
+            let color = match status {
+                Status::Previewed | Status::Cleaned => Color::Yellow,
+                Status::MemoryReleased => Color::Green,
+                Status::Hint => Color::Magenta,
+                Status::Info | Status::Running(_) => Color::White,
             };
             ratatui::text::Line::from(ratatui::text::Span::styled(msg, Style::default().fg(color)))
         }).collect::<Vec<_>>()
@@ -674,4 +1360,200 @@ fn draw_ui(frame: &mut ratatui::Frame, app: &App) {
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::LightCyan)));
     frame.render_widget(messages_widget, main_layout[1]);
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RuleConfig;
+
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+
+    fn write(path: &Path, days_old: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "data").unwrap();
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - DAY * days_old as u32).unwrap();
+    }
+
+    // 计划中的相对路径，目录以 / 结尾
+    fn planned(target: &Target) -> Vec<String> {
+        let mounts = Mounts::load(&ScanConfig::default());
+        let mut paths: Vec<String> = plan_directory(target, &mounts)
+            .iter()
+            .map(|entry| {
+                let relative = entry.path.strip_prefix(&target.path).unwrap().to_string_lossy().replace('\\', "/");
+                if entry.is_dir { format!("{}/", relative) } else { relative }
+            })
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    fn all_rules() -> RuleList {
+        RuleList::preset(RuleSet::All, Path::new(""))
+    }
+
+    #[test]
+    fn emptied_directories_are_planned() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(&root.join("empty/a.tmp"), 0);
+        write(&root.join("empty/nested/b.tmp"), 0);
+        write(&root.join("partial/c.tmp"), 0);
+        write(&root.join("partial/d.keep"), 0);
+
+        // 目录中有被排除的文件时不会被清空，目录本身保留
+        let rules = RuleList::try_from(vec![RuleConfig {
+            name: None,
+            include: vec!["*".to_string()],
+            exclude: vec!["*.keep".to_string()],
+            min_age_days: 0,
+            min_size_kb: 0,
+        }]).unwrap();
+        let target = Target::new("test", root).with_rules(rules);
+        assert_eq!(planned(&target), ["empty/", "empty/a.tmp", "empty/nested/", "empty/nested/b.tmp", "partial/c.tmp"]);
+
+        // 只按扩展名匹配时目录不属于任何规则，清空后也保留
+        let target = Target::new("test", root).with_rules(RuleList::preset(RuleSet::TempFiles, root));
+        assert_eq!(planned(&target), ["empty/a.tmp", "empty/nested/b.tmp", "partial/c.tmp"]);
+    }
+
+    #[test]
+    fn kept_directories_stay() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(&root.join(".X11-unix/X0"), 0);
+        write(&root.join("other/a"), 0);
+
+        let target = Target::new("test", root).with_rules(all_rules()).with_keep(&[".X11-unix"]);
+        assert_eq!(planned(&target), [".X11-unix/X0", "other/", "other/a"]);
+    }
+
+    #[test]
+    fn keep_newest_and_min_age_per_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        for (name, days) in [("a", 1), ("b", 5), ("c", 10), ("d", 20)] {
+            write(&root.join("dir").join(name), days);
+        }
+        write(&root.join("other/e"), 30);
+
+        let mut target = Target::new("test", root).with_rules(all_rules());
+        target.retention.keep_newest = 1;
+        // dir 中保留最新的 a，other 中唯一的文件也保留，两个目录都不会被清空
+        assert_eq!(planned(&target), ["dir/b", "dir/c", "dir/d"]);
+
+        let target = target.with_min_age(7);
+        assert_eq!(planned(&target), ["dir/c", "dir/d"]);
+    }
+
+    #[test]
+    fn rotated_logs_keep_generations() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(&root.join("syslog"), 0);
+        for (generation, days) in [(1, 1), (2, 2), (3, 3), (4, 40), (5, 50)] {
+            write(&root.join(format!("syslog.{}", generation)), days);
+        }
+        write(&root.join("syslog.6.gz"), 60);
+        write(&root.join("syslog.7.gz"), 200);
+        write(&root.join("apt/history.log.1.gz"), 1);
+
+        let log_scan = LogScanConfig { compress: false, ..LogScanConfig::default() };
+        let target = Target::new("var-log", root).with_rotated_logs(log_scan);
+        // 保留最新的 3 代，更旧的未压缩文件超过 30 天、压缩文件超过 180 天才删除；正在写入的 syslog 不处理
+        assert_eq!(planned(&target), ["syslog.4", "syslog.5", "syslog.7.gz"]);
+    }
+
+    #[test]
+    fn min_age_keeps_recent_files_and_their_directory() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        write(&root.join("dir/old"), 10);
+        write(&root.join("dir/new"), 0);
+
+        let target = Target::new("test", root).with_rules(all_rules()).with_min_age(7);
+        assert_eq!(planned(&target), ["dir/old"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn builtin_targets_follow_platform() {
+        let temp = tempfile::tempdir().unwrap();
+        let (config_home, cache_home) = (temp.path().join("config"), temp.path().join("cache"));
+        fs::create_dir_all(config_home.join("chromium")).unwrap();
+        fs::write(config_home.join("chromium/Local State"), r#"{"profile":{"info_cache":{"Work":{}}}}"#).unwrap();
+        let platform = platform::Fake {
+            folders: vec![(KnownFolder::Config, config_home.clone()), (KnownFolder::Cache, cache_home.clone())],
+            ..platform::Fake::default()
+        };
+
+        let targets = get_cached_targets(&Config::default(), &platform);
+        assert!(targets.iter().any(|target| target.name == "xdg-cache" && target.path == cache_home));
+        assert!(targets.iter().any(|target| target.name == "chromium" && target.path == cache_home.join("chromium/Work")));
+        // 包管理器的缓存只在管理员权限下清理
+        assert!(!targets.iter().any(|target| matches!(target.kind, TargetKind::PackageCache(_))));
+
+        // 找不到用户目录时没有依赖它们的目标
+        let targets = get_cached_targets(&Config::default(), &platform::Fake::default());
+        assert!(!targets.iter().any(|target| target.name == "xdg-cache" || target.name == "chromium"));
+    }
+
+    #[test]
+    fn app_hints_when_not_admin() {
+        static USER: platform::Fake = platform::Fake::new(false);
+        static ADMIN: platform::Fake = platform::Fake::new(true);
+        let has_hint = |platform: &'static platform::Fake| {
+            App::new(false, Vec::new(), Arc::new(Config::default()), platform)
+                .messages
+                .iter()
+                .any(|(status, message)| *status == Status::Hint && message.contains("管理员"))
+        };
+        assert!(has_hint(&USER));
+        assert!(!has_hint(&ADMIN));
+    }
+
+    #[test]
+    fn disabled_notification_is_not_sent() {
+        static PLATFORM: platform::Fake = platform::Fake::new(false);
+        let notification = NotificationConfig { enabled: false, timeout_ms: 1000 };
+        send_completion_notification(&PLATFORM, &notification, 2, 2048, 5, 0);
+        assert!(PLATFORM.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn completion_notification_is_sent_through_platform() {
+        static PLATFORM: platform::Fake = platform::Fake::new(false);
+        let notification = NotificationConfig { enabled: true, timeout_ms: 1000 };
+        send_completion_notification(&PLATFORM, &notification, 2, 2048, 5, 0);
+        // 通知在后台任务中发送
+        while PLATFORM.calls.lock().unwrap().is_empty() {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(*PLATFORM.calls.lock().unwrap(),
+            ["notify 磁盘清理完成: 清理完成！清理了 2 个文件/目录，释放了 2.0 KB，优化了 5 个进程内存"]);
+    }
+
+    #[test]
+    fn summary_snapshots_each_root_once() {
+        let temp = tempfile::tempdir().unwrap();
+        let entry = |root: &Path, name: &str, action: Action| PlanEntry {
+            target: "test".to_string(),
+            root: root.to_path_buf(),
+            path: root.join(name),
+            size: 1,
+            is_dir: false,
+            rule: String::new(),
+            category: "",
+            time: None,
+            action,
+        };
+        let plan: Vec<PlanEntry> = (0..100)
+            .map(|index| entry(temp.path(), &format!("{}.tmp", index), Action::Delete))
+            .chain([entry(Path::new("/nonexistent/docker.sock"), "image/sha256:1", Action::Prune)])
+            .collect();
+        let summary = CleanSummary::begin(&plan);
+        assert_eq!(summary.filesystems.len(), 1);
+        assert!(temp.path().starts_with(&summary.filesystems[0].root));
+    }
+}
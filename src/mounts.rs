@@ -0,0 +1,157 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::config::ScanConfig;
+
+// 扫描时的挂载边界：开启 one_file_system 时不进入任何其他文件系统或挂载点（包括绑定挂载），
+// 否则只跳过 excluded_fs_types 中的文件系统类型
+pub struct Mounts {
+    one_file_system: bool,
+    excluded_fs_types: Vec<String>,
+    table: Vec<(PathBuf, String)>, // 挂载点和文件系统类型
+}
+
+impl Mounts {
+    pub fn load(config: &ScanConfig) -> Self {
+        Self {
+            one_file_system: config.one_file_system,
+            excluded_fs_types: config.excluded_fs_types.clone(),
+            table: read_mount_table(),
+        }
+    }
+
+    // 扫描的起点本身是否允许，起点位于被排除的文件系统上时整个跳过
+    pub fn allows_root(&self, root: &Path) -> bool {
+        self.fs_type(root).is_none_or(|fs_type| !self.is_excluded(fs_type))
+    }
+
+    // 子目录 dir 是否是挂载点：设备号与上级目录不同，或出现在挂载表中（绑定挂载的设备号相同）
+    pub fn is_boundary(&self, parent: &fs::Metadata, dir: &Path, metadata: &fs::Metadata) -> bool {
+        !same_device(parent, metadata) || self.table.iter().any(|(mount_point, _)| mount_point == dir)
+    }
+
+    // 遍历时能否从 parent 进入子目录 dir
+    pub fn allows_dir(&self, parent: &fs::Metadata, dir: &Path, metadata: &fs::Metadata) -> bool {
+        !self.is_boundary(parent, dir, metadata) || (!self.one_file_system && self.allows_root(dir))
+    }
+
+    // 挂载表中的挂载点，跳过被排除的文件系统
+    #[cfg(unix)]
+    pub fn mount_points(&self) -> impl Iterator<Item = &Path> {
+        self.table
+            .iter()
+            .filter(|(_, fs_type)| !self.is_excluded(fs_type))
+            .map(|(mount_point, _)| mount_point.as_path())
+    }
+
+    // 路径所在挂载点（最长前缀）的文件系统类型
+    fn fs_type(&self, path: &Path) -> Option<&str> {
+        self.table
+            .iter()
+            .filter(|(mount_point, _)| path.starts_with(mount_point))
+            .max_by_key(|(mount_point, _)| mount_point.components().count())
+            .map(|(_, fs_type)| fs_type.as_str())
+    }
+
+    // "fuse" 同时匹配 "fuse.sshfs" 这样的子类型
+    fn is_excluded(&self, fs_type: &str) -> bool {
+        self.excluded_fs_types.iter().any(|excluded| {
+            fs_type == excluded || fs_type.strip_prefix(excluded.as_str()).is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+}
+
+#[cfg(unix)]
+fn same_device(a: &fs::Metadata, b: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    a.dev() == b.dev()
+}
+
+#[cfg(not(unix))]
+fn same_device(_a: &fs::Metadata, _b: &fs::Metadata) -> bool {
+    true
+}
+
+// /proc/self/mountinfo 每行：ID 父ID 主:次 根 挂载点 选项 [可选字段...] - 类型 来源 超级块选项
+#[cfg(target_os = "linux")]
+fn read_mount_table() -> Vec<(PathBuf, String)> {
+    let Ok(content) = fs::read_to_string("/proc/self/mountinfo") else {
+        return Vec::new();
+    };
+
+    content
+        .lines()
+        .filter_map(|line| {
+            let (mount, fs) = line.split_once(" - ")?;
+            let mount_point = mount.split(' ').nth(4)?;
+            let fs_type = fs.split(' ').next()?;
+            Some((PathBuf::from(unescape_mount_path(mount_point)), fs_type.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_mount_table() -> Vec<(PathBuf, String)> {
+    Vec::new()
+}
+
+// 挂载点中的空格、制表符、换行和反斜杠以 \ooo 八进制转义
+#[cfg(target_os = "linux")]
+fn unescape_mount_path(path: &str) -> String {
+    let bytes = path.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = bytes.get(index + 1..index + 4)
+            .filter(|_| bytes[index] == b'\\')
+            .and_then(|octal| u8::from_str_radix(std::str::from_utf8(octal).ok()?, 8).ok());
+        match escaped {
+            Some(byte) => {
+                result.push(byte);
+                index += 4;
+            }
+            None => {
+                result.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn unescape_octal_sequences() {
+        assert_eq!(unescape_mount_path("/mnt/usb\\040disk"), "/mnt/usb disk");
+        assert_eq!(unescape_mount_path("/a\\011b\\012c\\134d"), "/a\tb\nc\\d");
+        // 不完整或不是八进制的转义保持原样
+        assert_eq!(unescape_mount_path("/a\\04"), "/a\\04");
+        assert_eq!(unescape_mount_path("/a\\089"), "/a\\089");
+    }
+
+    #[test]
+    fn excluded_fs_types_match_subtypes() {
+        let mounts = Mounts {
+            one_file_system: false,
+            excluded_fs_types: vec!["fuse".to_string(), "nfs".to_string()],
+            table: vec![
+                (PathBuf::from("/"), "ext4".to_string()),
+                (PathBuf::from("/mnt/remote"), "fuse.sshfs".to_string()),
+                (PathBuf::from("/mnt/disk"), "fuseblk".to_string()),
+            ],
+        };
+        assert!(mounts.is_excluded("fuse.sshfs"));
+        assert!(mounts.is_excluded("nfs"));
+        assert!(!mounts.is_excluded("fuseblk"));
+        assert!(!mounts.is_excluded("nfs4"));
+        assert!(!mounts.allows_root(Path::new("/mnt/remote/data")));
+        assert!(mounts.allows_root(Path::new("/mnt/disk/data")));
+        assert!(mounts.allows_root(Path::new("/home")));
+    }
+}
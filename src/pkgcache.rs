@@ -0,0 +1,274 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::SystemTime,
+};
+
+use crate::{
+    config::{PackageCacheConfig, RuleConfig},
+    rules::RuleList,
+};
+
+// 系统包管理器的下载缓存。规则在扫描时才生成：读取已安装的包，每个包保留已安装的版本和最新的 keep_versions 个版本
+// （与 paccache -rk<N> 相同，新旧按下载时间判断），其余被取代的版本删除。读不到已安装的包时整个目标不清理
+#[derive(Clone, Copy)]
+enum Manager {
+    Apt,
+    Dnf,
+    Pacman,
+    Zypper,
+}
+
+#[derive(Clone)]
+pub struct PackageCache {
+    manager: Manager,
+    dir: PathBuf,
+    keep_versions: usize,
+}
+
+// 缓存中的一个包文件；id 与已安装包的标识格式相同，(name, arch) 相同的文件是同一个包的不同版本
+struct PackageFile {
+    relative: String,
+    name: String,
+    arch: String,
+    id: String,
+    time: Option<SystemTime>,
+}
+
+// dnf 的缓存在 <仓库>/packages/ 下，zypper 在 <仓库>/<架构>/ 下
+const MAX_DEPTH: usize = 3;
+
+// 返回各包管理器的目标名称、缓存目录和规则生成器
+pub fn targets(config: &PackageCacheConfig) -> Vec<(&'static str, PathBuf, PackageCache)> {
+    [
+        ("apt", Manager::Apt, "/var/cache/apt/archives"),
+        ("dnf", Manager::Dnf, "/var/cache/dnf"),
+        ("dnf5", Manager::Dnf, "/var/cache/libdnf5"),
+        ("pacman", Manager::Pacman, "/var/cache/pacman/pkg"),
+        ("zypper", Manager::Zypper, "/var/cache/zypp/packages"),
+    ]
+    .into_iter()
+    .filter(|(_, _, dir)| Path::new(dir).is_dir())
+    .map(|(name, manager, dir)| {
+        (name, PathBuf::from(dir), PackageCache { manager, dir: PathBuf::from(dir), keep_versions: config.keep_versions })
+    })
+    .collect()
+}
+
+impl PackageCache {
+    pub fn rules(&self) -> RuleList {
+        // pacman 正在运行时数据库被锁定，这时已安装的包随时可能变化
+        if matches!(self.manager, Manager::Pacman) && Path::new("/var/lib/pacman/db.lck").exists() {
+            return RuleList::default();
+        }
+        let Some(installed) = self.installed() else {
+            return RuleList::default();
+        };
+
+        let mut files = Vec::new();
+        self.find_files(&self.dir, 0, &mut files);
+        let mut packages: BTreeMap<(String, String), Vec<PackageFile>> = BTreeMap::new();
+        for file in files {
+            let parent = file.relative.rsplit_once('/').map(|(parent, _)| parent.to_string()).unwrap_or_default();
+            packages.entry((parent, format!("{}\0{}", file.name, file.arch))).or_default().push(file);
+        }
+
+        let mut superseded = Vec::new();
+        for mut versions in packages.into_values() {
+            versions.sort_by_key(|file| Reverse(file.time));
+            // 签名文件（.sig）与包文件的 id 相同，一起保留或删除
+            let mut kept_ids: Vec<&str> = Vec::new();
+            for file in &versions {
+                if !kept_ids.contains(&file.id.as_str()) && (installed.contains(&file.id) || kept_ids.len() < self.keep_versions) {
+                    kept_ids.push(&file.id);
+                }
+            }
+            superseded.extend(
+                versions
+                    .iter()
+                    .filter(|file| !kept_ids.contains(&file.id.as_str()))
+                    .map(|file| format!("/{}", globset::escape(&file.relative))),
+            );
+        }
+
+        let name = self.name();
+        let mut rules = Vec::new();
+        if !superseded.is_empty() {
+            rules.push(rule(format!("{}-superseded", name), superseded, 0));
+        }
+        // 中断的下载；正在进行的下载不会超过一天
+        if matches!(self.manager, Manager::Apt) {
+            rules.push(rule("apt-partial".to_string(), vec!["/partial/*".to_string()], 1));
+        }
+        RuleList::try_from(rules).unwrap_or_default()
+    }
+
+    fn name(&self) -> &'static str {
+        match self.manager {
+            Manager::Apt => "apt",
+            Manager::Dnf => "dnf",
+            Manager::Pacman => "pacman",
+            Manager::Zypper => "zypper",
+        }
+    }
+
+    // 已安装包的标识：apt 为 "名称_版本_架构"（版本中的 : 写作 %3a，与 .deb 文件名一致），
+    // pacman 为 "名称-版本-发布号"，rpm 为 "名称-版本-发布号.架构"
+    fn installed(&self) -> Option<HashSet<String>> {
+        match self.manager {
+            Manager::Apt => dpkg_installed(&fs::read_to_string("/var/lib/dpkg/status").ok()?),
+            Manager::Pacman => {
+                let entries = fs::read_dir("/var/lib/pacman/local").ok()?;
+                Some(
+                    entries
+                        .filter_map(Result::ok)
+                        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+                        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                        .collect(),
+                )
+            }
+            Manager::Dnf | Manager::Zypper => {
+                let output = Command::new("rpm")
+                    .args(["-qa", "--queryformat", "%{NAME}-%{VERSION}-%{RELEASE}.%{ARCH}\\n"])
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())?;
+                let installed: HashSet<String> = String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect();
+                (!installed.is_empty()).then_some(installed)
+            }
+        }
+    }
+
+    // 不跟随符号链接
+    fn find_files(&self, dir: &Path, depth: usize, files: &mut Vec<PackageFile>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.filter_map(Result::ok) {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            if metadata.is_dir() && depth < MAX_DEPTH {
+                self.find_files(&path, depth + 1, files);
+            } else if metadata.is_file()
+                && let Some(file_name) = entry.file_name().to_str()
+                && let Some((name, arch, id)) = self.parse_file_name(file_name)
+                && let Some(relative) = path.strip_prefix(&self.dir).ok().and_then(Path::to_str)
+            {
+                files.push(PackageFile {
+                    relative: relative.replace('\\', "/"),
+                    name,
+                    arch,
+                    id,
+                    time: metadata.modified().ok(),
+                });
+            }
+        }
+    }
+
+    // 从包文件名拆出 (名称, 架构, 标识)
+    fn parse_file_name(&self, file_name: &str) -> Option<(String, String, String)> {
+        match self.manager {
+            // 名称_版本_架构.deb
+            Manager::Apt => {
+                let id = file_name.strip_suffix(".deb")?;
+                let (name, rest) = id.split_once('_')?;
+                let (_, arch) = rest.rsplit_once('_')?;
+                Some((name.to_string(), arch.to_string(), id.to_string()))
+            }
+            // 名称-版本-发布号-架构.pkg.tar.zst，以及对应的 .sig
+            Manager::Pacman => {
+                let (stem, _) = file_name.split_once(".pkg.tar")?;
+                let (id, arch) = stem.rsplit_once('-')?;
+                let (name, _) = id.rsplit_once('-')?.0.rsplit_once('-')?;
+                Some((name.to_string(), arch.to_string(), id.to_string()))
+            }
+            // 名称-版本-发布号.架构.rpm
+            Manager::Dnf | Manager::Zypper => {
+                let id = file_name.strip_suffix(".rpm")?;
+                let (nvr, arch) = id.rsplit_once('.')?;
+                let (name, _) = nvr.rsplit_once('-')?.0.rsplit_once('-')?;
+                Some((name.to_string(), arch.to_string(), id.to_string()))
+            }
+        }
+    }
+}
+
+// status 文件中各段落的 Package、Version、Architecture 和 Status 字段，只取已安装的包
+fn dpkg_installed(status: &str) -> Option<HashSet<String>> {
+    let mut installed = HashSet::new();
+    for paragraph in status.split("\n\n") {
+        let field = |name: &str| {
+            paragraph
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .map(str::trim)
+        };
+        if !field("Status").is_some_and(|status| status.ends_with(" installed")) {
+            continue;
+        }
+        if let (Some(package), Some(version), Some(arch)) = (field("Package"), field("Version"), field("Architecture")) {
+            installed.insert(format!("{}_{}_{}", package, version.replace(':', "%3a"), arch));
+        }
+    }
+    (!installed.is_empty()).then_some(installed)
+}
+
+fn rule(name: String, include: Vec<String>, min_age_days: u64) -> RuleConfig {
+    RuleConfig { name: Some(name), include, exclude: Vec::new(), min_age_days, min_size_kb: 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(manager: Manager, file_name: &str) -> Option<(String, String, String)> {
+        PackageCache { manager, dir: PathBuf::new(), keep_versions: 1 }.parse_file_name(file_name)
+    }
+
+    fn parsed(name: &str, arch: &str, id: &str) -> Option<(String, String, String)> {
+        Some((name.to_string(), arch.to_string(), id.to_string()))
+    }
+
+    #[test]
+    fn parse_deb_names() {
+        assert_eq!(parse(Manager::Apt, "bash_5.2.15-2_amd64.deb"), parsed("bash", "amd64", "bash_5.2.15-2_amd64"));
+        assert_eq!(parse(Manager::Apt, "libc6_1%3a2.36-9_arm64.deb"), parsed("libc6", "arm64", "libc6_1%3a2.36-9_arm64"));
+        assert_eq!(parse(Manager::Apt, "lock"), None);
+        assert_eq!(parse(Manager::Apt, "broken.deb"), None);
+    }
+
+    #[test]
+    fn parse_pacman_names() {
+        assert_eq!(parse(Manager::Pacman, "linux-6.9.1.arch1-1-x86_64.pkg.tar.zst"),
+            parsed("linux", "x86_64", "linux-6.9.1.arch1-1"));
+        // 签名文件与包文件的标识相同
+        assert_eq!(parse(Manager::Pacman, "python-pip-24.0-1-any.pkg.tar.zst.sig"),
+            parsed("python-pip", "any", "python-pip-24.0-1"));
+        assert_eq!(parse(Manager::Pacman, "pkg.tar.zst"), None);
+    }
+
+    #[test]
+    fn parse_rpm_names() {
+        assert_eq!(parse(Manager::Dnf, "kernel-core-6.8.9-300.fc40.x86_64.rpm"),
+            parsed("kernel-core", "x86_64", "kernel-core-6.8.9-300.fc40.x86_64"));
+        assert_eq!(parse(Manager::Zypper, "vim-9.1.0-1.1.noarch.rpm"), parsed("vim", "noarch", "vim-9.1.0-1.1.noarch"));
+        assert_eq!(parse(Manager::Dnf, "repomd.xml"), None);
+    }
+
+    #[test]
+    fn dpkg_status_only_counts_installed() {
+        let status = "Package: bash\nStatus: install ok installed\nVersion: 5.2.15-2\nArchitecture: amd64\n\n\
+            Package: libc6\nStatus: install ok installed\nVersion: 1:2.36-9\nArchitecture: arm64\n\n\
+            Package: old\nStatus: deinstall ok config-files\nVersion: 1.0\nArchitecture: all\n";
+        let installed = dpkg_installed(status).unwrap();
+        assert_eq!(installed.len(), 2);
+        assert!(installed.contains("bash_5.2.15-2_amd64"));
+        assert!(installed.contains("libc6_1%3a2.36-9_arm64"));
+        assert!(dpkg_installed("Package: old\nStatus: deinstall ok config-files\n").is_none());
+    }
+}
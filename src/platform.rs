@@ -0,0 +1,361 @@
+use std::{io, path::PathBuf};
+
+use crate::{
+    config::{Config, MemoryConfig},
+    event::EventSender,
+};
+
+// 与操作系统相关的功能：释放进程内存、清空回收站、检测管理员权限、查找系统目录和发送桌面通知。
+// current() 按编译目标返回 Windows 或 Linux（及其他 Unix 系统）的实现，只在 main 中调用一次，
+// 其余代码通过参数取得 &dyn Platform，测试中传入 Fake
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum KnownFolder {
+    Home,         // 用户主目录
+    Data,         // 程序数据：%LOCALAPPDATA%，$XDG_DATA_HOME（默认 ~/.local/share）
+    Config,       // 用户配置：%APPDATA%，$XDG_CONFIG_HOME（默认 ~/.config）
+    Cache,        // 用户缓存：%LOCALAPPDATA%，$XDG_CACHE_HOME（默认 ~/.cache）
+    SystemConfig, // 系统配置：%ProgramData%，/etc
+}
+
+pub trait Platform: Sync {
+    // 释放内存，返回整理的进程数和测量到的释放量；各项操作的结果通过 sender 报告
+    fn trim_memory(&self, config: &MemoryConfig, sender: &EventSender) -> (usize, u64);
+    // 清空回收站，返回释放的字节数；单项失败通过 sender 报告
+    fn empty_trash(&self, config: &Config, sender: &EventSender) -> io::Result<u64>;
+    fn is_admin(&self) -> bool;
+    fn known_folder(&self, folder: KnownFolder) -> Option<PathBuf>;
+    fn notify(&self, summary: &str, body: &str, timeout_ms: u64) -> io::Result<()>;
+}
+
+pub fn current() -> &'static dyn Platform {
+    &imp::Native
+}
+
+fn show_notification(summary: &str, body: &str, timeout_ms: u64) -> io::Result<()> {
+    notify_rust::Notification::new()
+        .summary(summary)
+        .body(body)
+        .timeout(timeout_ms as i32)
+        .show()
+        .map(|_| ())
+        .map_err(io::Error::other)
+}
+
+#[cfg(windows)]
+mod imp {
+    use std::{cmp::Reverse, ffi::OsString, io, os::windows::ffi::OsStringExt, path::PathBuf};
+
+    use rayon::prelude::*;
+    use windows_sys::{
+        core::GUID,
+        Win32::{
+            Foundation::{CloseHandle, HANDLE},
+            System::{
+                Com::CoTaskMemFree,
+                ProcessStatus::{
+                    EmptyWorkingSet, EnumProcesses, GetModuleBaseNameW, GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS,
+                },
+                Threading::{OpenProcess, PROCESS_QUERY_INFORMATION, PROCESS_SET_QUOTA, PROCESS_VM_READ},
+            },
+            UI::Shell::{
+                FOLDERID_LocalAppData, FOLDERID_Profile, FOLDERID_ProgramData, FOLDERID_RoamingAppData, KF_FLAG_DEFAULT,
+                SHEmptyRecycleBinW, SHGetKnownFolderPath, SHQueryRecycleBinW, SHERB_NOCONFIRMATION, SHERB_NOPROGRESSUI,
+                SHERB_NOSOUND, SHQUERYRBINFO,
+            },
+        },
+    };
+
+    use super::{KnownFolder, Platform, show_notification};
+    use crate::{
+        config::{Config, MemoryConfig},
+        event::{Event, EventSender},
+    };
+
+    pub struct Native;
+
+    impl Platform for Native {
+        // 整理所有能打开的进程的工作集，按整理前后的 WorkingSetSize 计算各进程的释放量，报告释放最多的 top_processes 个
+        fn trim_memory(&self, config: &MemoryConfig, sender: &EventSender) -> (usize, u64) {
+            let process_ids = unsafe {
+                let mut pids = Vec::with_capacity(1024);
+                let mut cb_needed = 0;
+                if EnumProcesses(pids.as_mut_ptr(), (pids.capacity() * std::mem::size_of::<u32>()) as u32, &mut cb_needed) != 0 {
+                    pids.set_len((cb_needed / std::mem::size_of::<u32>() as u32) as usize);
+                    pids
+                } else {
+                    let error = io::Error::last_os_error();
+                    sender.send(Event::MemoryActionFailed { action: "枚举进程".to_string(), error }).ok();
+                    Vec::new()
+                }
+            };
+
+            let mut trimmed: Vec<TrimmedProcess> = process_ids.par_iter().filter_map(|&pid| trim_process(pid)).collect();
+            trimmed.sort_by_key(|process| Reverse(process.freed()));
+            for process in trimmed.iter().take(config.top_processes).filter(|process| process.freed() > 0) {
+                sender.send(Event::ProcessTrimmed {
+                    pid: process.pid,
+                    name: process.name.clone(),
+                    before: process.before,
+                    after: process.after,
+                }).ok();
+            }
+            (trimmed.len(), trimmed.iter().map(TrimmedProcess::freed).sum())
+        }
+
+        // 清空所有驱动器的回收站，返回清空前回收站占用的大小
+        fn empty_trash(&self, _config: &Config, _sender: &EventSender) -> io::Result<u64> {
+            unsafe {
+                let mut info = SHQUERYRBINFO {
+                    cbSize: std::mem::size_of::<SHQUERYRBINFO>() as u32,
+                    i64Size: 0,
+                    i64NumItems: 0,
+                };
+                let bytes = if SHQueryRecycleBinW(std::ptr::null(), &mut info) == 0 {
+                    info.i64Size.max(0) as u64
+                } else {
+                    0
+                };
+
+                let result = SHEmptyRecycleBinW(
+                    std::ptr::null_mut(), // 所有驱动器
+                    std::ptr::null(),     // 清空所有文件
+                    SHERB_NOCONFIRMATION | SHERB_NOPROGRESSUI | SHERB_NOSOUND, // 静默清空
+                );
+
+                if result == 0 {
+                    Ok(bytes)
+                } else {
+                    Err(io::Error::other(format!("HRESULT 0x{:08X}", result)))
+                }
+            }
+        }
+
+        fn is_admin(&self) -> bool {
+            is_admin::is_admin()
+        }
+
+        fn known_folder(&self, folder: KnownFolder) -> Option<PathBuf> {
+            let id = match folder {
+                KnownFolder::Home => FOLDERID_Profile,
+                KnownFolder::Data | KnownFolder::Cache => FOLDERID_LocalAppData,
+                KnownFolder::Config => FOLDERID_RoamingAppData,
+                KnownFolder::SystemConfig => FOLDERID_ProgramData,
+            };
+            known_folder_path(&id)
+        }
+
+        fn notify(&self, summary: &str, body: &str, timeout_ms: u64) -> io::Result<()> {
+            show_notification(summary, body, timeout_ms)
+        }
+    }
+
+    struct TrimmedProcess {
+        pid: u32,
+        name: String,
+        before: u64,
+        after: u64,
+    }
+
+    impl TrimmedProcess {
+        fn freed(&self) -> u64 {
+            self.before.saturating_sub(self.after)
+        }
+    }
+
+    // EmptyWorkingSet 需要 PROCESS_SET_QUOTA；无法打开或读取工作集的进程（系统进程、权限不足）跳过
+    fn trim_process(pid: u32) -> Option<TrimmedProcess> {
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_INFORMATION | PROCESS_VM_READ | PROCESS_SET_QUOTA, 0, pid);
+            if handle.is_null() {
+                return None;
+            }
+            let trimmed = match working_set(handle) {
+                Some(before) if EmptyWorkingSet(handle) != 0 => Some(TrimmedProcess {
+                    pid,
+                    name: process_name(handle),
+                    before,
+                    after: working_set(handle).unwrap_or(before),
+                }),
+                _ => None,
+            };
+            CloseHandle(handle);
+            trimmed
+        }
+    }
+
+    unsafe fn working_set(handle: HANDLE) -> Option<u64> {
+        let mut counters = std::mem::MaybeUninit::<PROCESS_MEMORY_COUNTERS>::uninit();
+        let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+        unsafe {
+            (GetProcessMemoryInfo(handle, counters.as_mut_ptr(), size) != 0)
+                .then(|| counters.assume_init().WorkingSetSize as u64)
+        }
+    }
+
+    // 进程主模块的文件名，例如 chrome.exe
+    unsafe fn process_name(handle: HANDLE) -> String {
+        let mut name = [0u16; 260];
+        let len = unsafe { GetModuleBaseNameW(handle, std::ptr::null_mut(), name.as_mut_ptr(), name.len() as u32) };
+        if len == 0 {
+            return "?".to_string();
+        }
+        String::from_utf16_lossy(&name[..len as usize])
+    }
+
+    fn known_folder_path(id: &GUID) -> Option<PathBuf> {
+        unsafe {
+            let mut path = std::ptr::null_mut();
+            let result = SHGetKnownFolderPath(id, KF_FLAG_DEFAULT as u32, std::ptr::null_mut(), &mut path);
+            // 失败时同样需要释放返回的缓冲区
+            let folder = (result == 0 && !path.is_null()).then(|| {
+                let len = (0..).take_while(|&i| *path.add(i) != 0).count();
+                PathBuf::from(OsString::from_wide(std::slice::from_raw_parts(path, len)))
+            });
+            CoTaskMemFree(path as *const _);
+            folder
+        }
+    }
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::{
+        env, io,
+        path::{Path, PathBuf},
+    };
+
+    use super::{KnownFolder, Platform, show_notification};
+    use crate::{
+        config::{Config, MemoryConfig},
+        event::EventSender,
+        mounts::Mounts,
+        reclaim, trash,
+    };
+
+    pub struct Native;
+
+    impl Platform for Native {
+        // 按配置丢弃缓存、整理碎片、换出指定进程或回收 cgroup 的内存，默认不做任何操作
+        fn trim_memory(&self, config: &MemoryConfig, sender: &EventSender) -> (usize, u64) {
+            reclaim::release(config, self.is_admin(), sender)
+        }
+
+        // freedesktop.org 回收站，只清理移入超过 trash.min_age_days 的项
+        fn empty_trash(&self, config: &Config, sender: &EventSender) -> io::Result<u64> {
+            let data_home = self.known_folder(KnownFolder::Data);
+            Ok(trash::purge(&config.trash, data_home.as_deref(), &Mounts::load(&config.scan), sender))
+        }
+
+        fn is_admin(&self) -> bool {
+            unsafe { libc::geteuid() == 0 }
+        }
+
+        // XDG 目录变量必须是绝对路径，否则按规范使用默认位置
+        fn known_folder(&self, folder: KnownFolder) -> Option<PathBuf> {
+            let (var, default) = match folder {
+                KnownFolder::Home => return env::var_os("HOME").map(PathBuf::from),
+                KnownFolder::SystemConfig => return Some(PathBuf::from("/etc")),
+                KnownFolder::Data => ("XDG_DATA_HOME", Path::new(".local").join("share")),
+                KnownFolder::Config => ("XDG_CONFIG_HOME", PathBuf::from(".config")),
+                KnownFolder::Cache => ("XDG_CACHE_HOME", PathBuf::from(".cache")),
+            };
+            env::var_os(var)
+                .map(PathBuf::from)
+                .filter(|dir| dir.is_absolute())
+                .or_else(|| self.known_folder(KnownFolder::Home).map(|home| home.join(default)))
+        }
+
+        fn notify(&self, summary: &str, body: &str, timeout_ms: u64) -> io::Result<()> {
+            show_notification(summary, body, timeout_ms)
+        }
+    }
+}
+
+// 不接触系统的实现：各项结果预先设定，调用记录在 calls 中
+#[cfg(test)]
+#[derive(Default)]
+pub struct Fake {
+    pub trimmed: usize,
+    pub trash: Option<u64>, // None 时清空回收站失败
+    pub admin: bool,
+    pub folders: Vec<(KnownFolder, PathBuf)>,
+    pub calls: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl Fake {
+    // 后台任务需要 &'static dyn Platform，测试中用它声明 static
+    pub const fn new(admin: bool) -> Self {
+        Self { trimmed: 0, trash: None, admin, folders: Vec::new(), calls: std::sync::Mutex::new(Vec::new()) }
+    }
+
+    fn record(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+}
+
+#[cfg(test)]
+impl Platform for Fake {
+    fn trim_memory(&self, _config: &MemoryConfig, _sender: &EventSender) -> (usize, u64) {
+        self.record("trim_memory".to_string());
+        (self.trimmed, 0)
+    }
+
+    fn empty_trash(&self, _config: &Config, _sender: &EventSender) -> io::Result<u64> {
+        self.record("empty_trash".to_string());
+        self.trash.ok_or_else(|| io::Error::other("回收站不可用"))
+    }
+
+    fn is_admin(&self) -> bool {
+        self.admin
+    }
+
+    fn known_folder(&self, folder: KnownFolder) -> Option<PathBuf> {
+        self.folders.iter().find(|(known, _)| *known == folder).map(|(_, path)| path.clone())
+    }
+
+    fn notify(&self, summary: &str, body: &str, _timeout_ms: u64) -> io::Result<()> {
+        self.record(format!("notify {}: {}", summary, body));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::{
+        empty_recycle_bin,
+        event::{Event, Phase},
+        release_memory,
+    };
+
+    #[test]
+    fn empty_recycle_bin_reports_result() {
+        let config = Config::default();
+        let (sender, receiver) = mpsc::channel();
+
+        let platform = Fake { trash: Some(4096), ..Fake::default() };
+        assert_eq!(empty_recycle_bin(&platform, &config, sender.clone()), Some(4096));
+        let failing = Fake::default();
+        assert_eq!(empty_recycle_bin(&failing, &config, sender), None);
+
+        let events: Vec<Event> = receiver.try_iter().collect();
+        assert!(matches!(events[1], Event::RecycleBinEmptied { bytes: 4096 }));
+        assert!(matches!(events[3], Event::RecycleBinFailed(_)));
+        assert_eq!(*platform.calls.lock().unwrap(), ["empty_trash"]);
+    }
+
+    #[test]
+    fn release_memory_reports_trimmed_processes() {
+        let (sender, receiver) = mpsc::channel();
+        let platform = Fake { trimmed: 3, ..Fake::default() };
+        release_memory(&platform, &MemoryConfig::default(), sender);
+
+        let events: Vec<Event> = receiver.try_iter().collect();
+        assert!(matches!(events[..], [Event::PhaseFinished { phase: Phase::ReleaseMemory, count: 3, bytes: 0 }]));
+        assert_eq!(*platform.calls.lock().unwrap(), ["trim_memory"]);
+    }
+}
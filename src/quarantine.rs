@@ -0,0 +1,526 @@
+use serde::{Deserialize, Serialize};
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    PlanEntry,
+    config::QuarantineConfig,
+    event::{Event, EventSender},
+    format_size, rooted, space,
+};
+
+// 每次运行一个隔离目录：<隔离区>/<run-id>/manifest.toml 记录所有被移走的项，
+// 实际内容放在 files/ 下；不在同一文件系统上的项放到该文件系统根目录下的隐藏隔离目录
+const MANIFEST_FILE_NAME: &str = "manifest.toml";
+const FILES_DIR_NAME: &str = "files";
+const HOLDING_DIR_NAME: &str = ".diskspace_free-quarantine";
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    run_id: String,
+    created: u64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    entries: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ManifestEntry {
+    original: PathBuf,
+    stored: PathBuf,
+    size: u64,
+    is_dir: bool,
+    mtime: Option<u64>, // 修改时间（Unix 秒），符号链接不记录
+    mode: Option<u32>,  // Unix 权限位
+    readonly: bool,
+    target: String,
+    rule: String,
+}
+
+// 追加写入清单时使用，序列化结果为一个 [[entries]] 表
+#[derive(Serialize)]
+struct ManifestAppend<'a> {
+    entries: [&'a ManifestEntry; 1],
+}
+
+pub struct RunInfo {
+    pub run_id: String,
+    pub created: u64,
+    pub entries: usize,
+    pub bytes: u64,
+}
+
+pub struct Quarantine {
+    run_id: String,
+    run_dir: PathBuf,
+    next_id: AtomicUsize,
+    manifest: Mutex<fs::File>,
+}
+
+impl Quarantine {
+    pub fn begin(config: &QuarantineConfig) -> io::Result<Self> {
+        let created = now_secs();
+        let run_id = format!("{}-{}", format_run_time(created), std::process::id());
+        let run_dir = config.dir.join(&run_id);
+        fs::create_dir_all(run_dir.join(FILES_DIR_NAME))?;
+
+        let mut manifest = fs::OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(run_dir.join(MANIFEST_FILE_NAME))?;
+        let header = Manifest { run_id: run_id.clone(), created, entries: Vec::new() };
+        manifest.write_all(toml::to_string(&header).map_err(io::Error::other)?.as_bytes())?;
+
+        Ok(Self { run_id, run_dir, next_id: AtomicUsize::new(0), manifest: Mutex::new(manifest) })
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    // 把计划中的一项移入隔离区：只做重命名，绝不跨文件系统复制。
+    // 清单写入失败时把项放回原处，隔离区中不会有没有记录的内容
+    pub fn hold(&self, entry: &PlanEntry) -> io::Result<()> {
+        let metadata = fs::symlink_metadata(&entry.path)?;
+        let name = self.next_id.fetch_add(1, Ordering::SeqCst).to_string();
+
+        let primary = self.run_dir.join(FILES_DIR_NAME).join(&name);
+        let stored = match rooted::rename(&entry.root, &entry.path, &primary) {
+            Ok(()) => primary,
+            Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+                let holding = self.holding_dir_for(&entry.path)?;
+                let stored = holding.join(&name);
+                rooted::rename(&entry.root, &entry.path, &stored)?;
+                stored
+            }
+            Err(err) => return Err(err),
+        };
+
+        let record = ManifestEntry {
+            original: entry.path.clone(),
+            stored,
+            size: entry.size,
+            is_dir: entry.is_dir,
+            mtime: (!metadata.file_type().is_symlink())
+                .then(|| metadata.modified().ok())
+                .flatten()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|age| age.as_secs()),
+            mode: permission_mode(&metadata),
+            readonly: metadata.permissions().readonly(),
+            target: entry.target.clone(),
+            rule: entry.rule.clone(),
+        };
+
+        if let Err(error) = self.append(&record) {
+            return match rooted::rename_back(&entry.root, &record.stored, &entry.path) {
+                Ok(()) => Err(error),
+                Err(rollback) => Err(io::Error::new(error.kind(),
+                    format!("{}; 无法放回原处, 仍在 {} ({})", error, record.stored.display(), rollback))),
+            };
+        }
+        Ok(())
+    }
+
+    fn append(&self, record: &ManifestEntry) -> io::Result<()> {
+        let text = toml::to_string(&ManifestAppend { entries: [record] }).map_err(io::Error::other)?;
+        let mut manifest = self.manifest.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        manifest.write_all(format!("\n{}", text).as_bytes())?;
+        manifest.flush()
+    }
+
+    // 与隔离区不在同一文件系统的项，放到其所在文件系统根目录下
+    fn holding_dir_for(&self, path: &Path) -> io::Result<PathBuf> {
+        let root = space::filesystem_root(path)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "无法确定所在文件系统"))?;
+        let holding = root.join(HOLDING_DIR_NAME).join(&self.run_id).join(FILES_DIR_NAME);
+        fs::create_dir_all(&holding)?;
+        Ok(holding)
+    }
+}
+
+pub fn list_runs(config: &QuarantineConfig) -> Vec<RunInfo> {
+    let mut runs: Vec<RunInfo> = read_manifests(config)
+        .into_iter()
+        .map(|(_, manifest)| RunInfo {
+            run_id: manifest.run_id,
+            created: manifest.created,
+            entries: manifest.entries.len(),
+            bytes: manifest.entries.iter().map(|entry| entry.size).sum(),
+        })
+        .collect();
+    runs.sort_by_key(|run| run.created);
+    runs
+}
+
+// 把一次运行隔离的内容放回原处；放不回去的项保留在清单中
+pub fn restore(config: &QuarantineConfig, run_id: &str, sender: EventSender) -> io::Result<usize> {
+    let run_dir = run_dir(config, run_id)?;
+    let manifest = read_manifest(&run_dir)?;
+    let stored: Vec<PathBuf> = manifest.entries.iter().map(|entry| entry.stored.clone()).collect();
+
+    let mut restored = 0;
+    let mut remaining = Vec::new();
+    for entry in manifest.entries {
+        match restore_entry(&run_dir, &entry) {
+            Ok(()) => {
+                sender.send(Event::Restored { path: entry.original.clone(), bytes: entry.size }).ok();
+                restored += 1;
+            }
+            Err(error) => {
+                sender.send(Event::RestoreFailed { path: entry.original.clone(), error }).ok();
+                remaining.push(entry);
+            }
+        }
+    }
+
+    if remaining.is_empty() {
+        // 所有项都已放回原处，只剩下空的隔离目录
+        remove_run_dirs(&run_dir, &stored);
+    } else {
+        let manifest = Manifest { run_id: manifest.run_id, created: manifest.created, entries: remaining };
+        fs::write(run_dir.join(MANIFEST_FILE_NAME), toml::to_string(&manifest).map_err(io::Error::other)?)?;
+    }
+    Ok(restored)
+}
+
+// 删除超过保留期限的隔离记录，返回释放的字节数
+pub fn expire(config: &QuarantineConfig, sender: EventSender) -> u64 {
+    let deadline = now_secs().saturating_sub(config.expire_days * 24 * 3600);
+    let mut freed = 0;
+
+    for (run_dir, manifest) in read_manifests(config) {
+        if manifest.created > deadline {
+            continue;
+        }
+
+        let bytes: u64 = manifest.entries.iter().map(|entry| entry.size).sum();
+        let stored: Vec<PathBuf> = manifest.entries.iter().map(|entry| entry.stored.clone()).collect();
+        remove_stored(&run_dir, &stored);
+        remove_run_dirs(&run_dir, &stored);
+        sender.send(Event::Progress(format!("已清除过期的隔离记录 {} ({} 项, {})",
+            manifest.run_id, manifest.entries.len(), format_size(bytes)))).ok();
+        freed += bytes;
+    }
+    freed
+}
+
+fn restore_entry(run_dir: &Path, entry: &ManifestEntry) -> io::Result<()> {
+    let stored = resolve_stored(run_dir, &entry.stored)?;
+    if fs::symlink_metadata(&entry.original).is_ok() {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, "原位置已存在同名文件"));
+    }
+    if let Some(parent) = entry.original.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::rename(&stored, &entry.original)?;
+    if fs::symlink_metadata(&entry.original)?.file_type().is_symlink() {
+        return Ok(());
+    }
+
+    // 先恢复修改时间再恢复权限，避免只读文件无法写入属性
+    if let Some(mtime) = entry.mtime {
+        let modified = UNIX_EPOCH + Duration::from_secs(mtime);
+        let file = if entry.is_dir {
+            fs::File::open(&entry.original)
+        } else {
+            fs::File::options().write(true).open(&entry.original)
+        };
+        if let Ok(file) = file {
+            file.set_modified(modified).ok();
+        }
+    }
+    let mut permissions = fs::metadata(&entry.original)?.permissions();
+    set_permission_mode(&mut permissions, entry.mode, entry.readonly);
+    fs::set_permissions(&entry.original, permissions)
+}
+
+// 命令行给出的运行 ID 只能是隔离区下的一个运行目录，不能是绝对路径或包含 ..
+fn run_dir(config: &QuarantineConfig, run_id: &str) -> io::Result<PathBuf> {
+    let mut components = Path::new(run_id).components();
+    let valid = matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) && is_run_id(run_id);
+    let run_dir = config.dir.join(run_id);
+    if !valid || !fs::symlink_metadata(&run_dir).is_ok_and(|metadata| metadata.is_dir()) {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("隔离区中没有运行记录 {}", run_id)));
+    }
+    Ok(run_dir)
+}
+
+// 运行 ID 的格式为 YYYYMMDD-HHMMSS-<进程号>
+fn is_run_id(name: &str) -> bool {
+    let parts: Vec<&str> = name.split('-').collect();
+    let digits = |part: &str, len: Option<usize>| {
+        !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit()) && len.is_none_or(|len| part.len() == len)
+    };
+    parts.len() == 3 && digits(parts[0], Some(8)) && digits(parts[1], Some(6)) && digits(parts[2], None)
+}
+
+// 清单中的项所在的 files/ 目录：本次运行目录下的，或其他文件系统根目录下以运行 ID 命名的隔离目录中的。
+// 不在这些位置的项不会被删除，防止清单被篡改后误删其他文件
+fn stored_files_dir<'a>(run_dir: &Path, stored: &'a Path) -> Option<&'a Path> {
+    let files_dir = stored.parent()?;
+    if files_dir == run_dir.join(FILES_DIR_NAME) {
+        return Some(files_dir);
+    }
+    let holding_run_dir = files_dir.parent()?;
+    let holding_root = holding_run_dir.parent()?;
+    let valid = files_dir.file_name().is_some_and(|name| name == FILES_DIR_NAME)
+        && holding_run_dir.file_name() == run_dir.file_name()
+        && holding_root.file_name().is_some_and(|name| name == HOLDING_DIR_NAME);
+    valid.then_some(files_dir)
+}
+
+// 清单中的存放位置解析符号链接后仍须是本次运行的 files/ 目录中的一项，
+// 否则清单被篡改或损坏时恢复会把任意文件移走
+fn resolve_stored(run_dir: &Path, stored: &Path) -> io::Result<PathBuf> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, format!("{} 不在隔离区中", stored.display()));
+    let name = stored.file_name().filter(|_| stored_files_dir(run_dir, stored).is_some()).ok_or_else(invalid)?;
+    let resolved = stored.parent().ok_or_else(invalid)?.canonicalize()?.join(name);
+    if stored_files_dir(&run_dir.canonicalize()?, &resolved).is_none() {
+        return Err(invalid());
+    }
+    Ok(resolved)
+}
+
+// 删除过期记录中隔离的内容
+fn remove_stored(run_dir: &Path, stored: &[PathBuf]) {
+    for path in stored.iter().filter(|path| stored_files_dir(run_dir, path).is_some()) {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_dir() => fs::remove_dir_all(path).ok(),
+            Ok(_) => fs::remove_file(path).ok(),
+            Err(_) => None,
+        };
+    }
+}
+
+// 删除已经清空的运行目录以及放在其他文件系统上的隔离目录；只删除空目录，
+// 其中还有内容（例如没有记录在清单中的文件）时保留
+fn remove_run_dirs(run_dir: &Path, stored: &[PathBuf]) {
+    let mut files_dirs: Vec<&Path> = stored.iter().filter_map(|path| stored_files_dir(run_dir, path)).collect();
+    files_dirs.sort();
+    files_dirs.dedup();
+    for files_dir in files_dirs {
+        let Some(holding_run_dir) = files_dir.parent().filter(|dir| *dir != run_dir) else {
+            continue;
+        };
+        if fs::remove_dir(files_dir).and_then(|()| fs::remove_dir(holding_run_dir)).is_ok()
+            && let Some(holding_root) = holding_run_dir.parent()
+        {
+            // 隔离目录已空时一并删除
+            fs::remove_dir(holding_root).ok();
+        }
+    }
+    // files/ 已空时才删除清单和运行目录
+    let files_dir = run_dir.join(FILES_DIR_NAME);
+    if fs::remove_dir(&files_dir).is_ok() || !files_dir.exists() {
+        fs::remove_file(run_dir.join(MANIFEST_FILE_NAME)).ok();
+        fs::remove_dir(run_dir).ok();
+    }
+}
+
+fn read_manifests(config: &QuarantineConfig) -> Vec<(PathBuf, Manifest)> {
+    let Ok(entries) = fs::read_dir(&config.dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_str().is_some_and(is_run_id))
+        .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+        .filter_map(|entry| {
+            let run_dir = entry.path();
+            let manifest = read_manifest(&run_dir).ok()?;
+            Some((run_dir, manifest))
+        })
+        .collect()
+}
+
+fn read_manifest(run_dir: &Path) -> io::Result<Manifest> {
+    let content = fs::read_to_string(run_dir.join(MANIFEST_FILE_NAME))?;
+    toml::from_str(&content).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+#[cfg(unix)]
+fn permission_mode(metadata: &fs::Metadata) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    Some(metadata.permissions().mode())
+}
+
+#[cfg(not(unix))]
+fn permission_mode(_metadata: &fs::Metadata) -> Option<u32> {
+    None
+}
+
+#[cfg(unix)]
+fn set_permission_mode(permissions: &mut fs::Permissions, mode: Option<u32>, readonly: bool) {
+    use std::os::unix::fs::PermissionsExt;
+    match mode {
+        Some(mode) => permissions.set_mode(mode),
+        None => permissions.set_readonly(readonly),
+    }
+}
+
+#[cfg(not(unix))]
+fn set_permission_mode(permissions: &mut fs::Permissions, _mode: Option<u32>, readonly: bool) {
+    permissions.set_readonly(readonly);
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or(0)
+}
+
+// Unix 秒转为 UTC 时间 YYYYMMDD-HHMMSS，用作运行 ID
+pub fn format_run_time(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+
+    // 公历日期换算（Howard Hinnant 的 civil_from_days 算法）
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::Action;
+
+    fn config(dir: &Path) -> QuarantineConfig {
+        QuarantineConfig { enabled: true, dir: dir.join("quarantine"), expire_days: 7 }
+    }
+
+    fn plan_entry(root: &Path, name: &str) -> PlanEntry {
+        let path = root.join(name);
+        PlanEntry {
+            target: "test".to_string(),
+            root: root.to_path_buf(),
+            size: fs::metadata(&path).unwrap().len(),
+            path,
+            is_dir: false,
+            rule: "all".to_string(),
+            category: "临时文件",
+            time: None,
+            action: Action::Delete,
+        }
+    }
+
+    #[test]
+    fn run_id_format() {
+        assert!(is_run_id("20240101-120000-4242"));
+        assert!(!is_run_id("20240101-120000"));
+        assert!(!is_run_id("2024011-120000-1"));
+        assert!(!is_run_id("20240101-120000-"));
+        assert!(!is_run_id("../20240101-120000-1"));
+    }
+
+    #[test]
+    fn restore_rejects_paths_outside_quarantine() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = config(temp.path());
+        fs::create_dir_all(&config.dir).unwrap();
+        let victim = temp.path().join("victim");
+        fs::create_dir(&victim).unwrap();
+        fs::write(victim.join(MANIFEST_FILE_NAME), "run_id = \"x\"\ncreated = 0\n").unwrap();
+        fs::write(victim.join("data"), "keep").unwrap();
+
+        let (sender, _receiver) = mpsc::channel();
+        for run_id in [victim.to_str().unwrap(), "../victim", "..", "victim", "20240101-120000-1"] {
+            assert!(restore(&config, run_id, sender.clone()).is_err(), "{}", run_id);
+        }
+        assert!(victim.join("data").exists());
+    }
+
+    #[test]
+    fn hold_and_restore() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = config(temp.path());
+        let root = temp.path().join("target");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("sub/a.tmp"), "12345").unwrap();
+
+        let quarantine = Quarantine::begin(&config).unwrap();
+        quarantine.hold(&plan_entry(&root, "sub/a.tmp")).unwrap();
+        assert!(!root.join("sub/a.tmp").exists());
+        let runs = list_runs(&config);
+        assert_eq!((runs.len(), runs[0].entries, runs[0].bytes), (1, 1, 5));
+
+        let run_id = quarantine.run_id().to_string();
+        drop(quarantine);
+        let (sender, _receiver) = mpsc::channel();
+        assert_eq!(restore(&config, &run_id, sender).unwrap(), 1);
+        assert_eq!(fs::read_to_string(root.join("sub/a.tmp")).unwrap(), "12345");
+        assert!(!config.dir.join(&run_id).exists());
+    }
+
+    #[test]
+    fn restore_rejects_stored_paths_outside_run() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = config(temp.path());
+        let root = temp.path().join("target");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("a.tmp"), "a").unwrap();
+        fs::write(root.join("b.tmp"), "b").unwrap();
+        let outside = temp.path().join("outside");
+        fs::write(&outside, "keep").unwrap();
+
+        let quarantine = Quarantine::begin(&config).unwrap();
+        quarantine.hold(&plan_entry(&root, "a.tmp")).unwrap();
+        quarantine.hold(&plan_entry(&root, "b.tmp")).unwrap();
+        let run_id = quarantine.run_id().to_string();
+        drop(quarantine);
+
+        // 篡改清单：一项指向隔离区之外的文件，一项指向 files/ 目录本身
+        let run_dir = config.dir.join(&run_id);
+        let mut manifest = read_manifest(&run_dir).unwrap();
+        manifest.entries[0].stored = outside.clone();
+        manifest.entries[1].stored = run_dir.join(FILES_DIR_NAME).join("..");
+        fs::write(run_dir.join(MANIFEST_FILE_NAME), toml::to_string(&manifest).unwrap()).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        assert_eq!(restore(&config, &run_id, sender).unwrap(), 0);
+        assert_eq!(receiver.try_iter().filter(|event| matches!(event, Event::RestoreFailed { .. })).count(), 2);
+        assert_eq!(fs::read_to_string(&outside).unwrap(), "keep");
+        assert!(!root.join("a.tmp").exists());
+        assert!(run_dir.join(FILES_DIR_NAME).join("1").exists());
+    }
+
+    #[test]
+    fn remove_run_dirs_keeps_unrecorded_content() {
+        let temp = tempfile::tempdir().unwrap();
+        let config = config(temp.path());
+        let run_dir = config.dir.join("20240101-120000-1");
+        fs::create_dir_all(run_dir.join(FILES_DIR_NAME)).unwrap();
+        fs::write(run_dir.join(MANIFEST_FILE_NAME), "").unwrap();
+        fs::write(run_dir.join(FILES_DIR_NAME).join("7"), "unrecorded").unwrap();
+
+        // 清单中指向隔离区之外的项不会被删除
+        let outside = temp.path().join("outside");
+        fs::write(&outside, "keep").unwrap();
+        remove_stored(&run_dir, std::slice::from_ref(&outside));
+        remove_run_dirs(&run_dir, std::slice::from_ref(&outside));
+        assert!(outside.exists());
+        assert!(run_dir.join(FILES_DIR_NAME).join("7").exists());
+
+        fs::remove_file(run_dir.join(FILES_DIR_NAME).join("7")).unwrap();
+        remove_run_dirs(&run_dir, &[]);
+        assert!(!run_dir.exists());
+    }
+}
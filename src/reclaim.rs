@@ -0,0 +1,355 @@
+use std::{cmp::Reverse, fs, io, path::Path};
+
+use crate::{
+    config::MemoryConfig,
+    event::{Event, EventSender},
+};
+
+// Linux 上释放内存的各项操作，都需要在配置中单独开启：丢弃页缓存（drop_caches）、整理内存碎片（compact_memory）、
+// 用 process_madvise(MADV_PAGEOUT) 把指定进程的内存换出，以及向 cgroup v2 的 memory.reclaim 请求回收。
+// 每项操作前后各测量一次效果，开始和结束时报告 /proc/meminfo
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+// 内存整理的效果按 2 MB（x86_64 大页）及以上的连续空闲块计算
+const COMPACT_ORDER: usize = 9;
+
+// /proc/meminfo 中用到的字段，单位为字节
+struct MemInfo {
+    total: u64,
+    available: u64,
+    free: u64,
+    cached: u64,
+}
+
+impl MemInfo {
+    fn read() -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string("/proc/meminfo")?))
+    }
+
+    // 缺少的字段按 0 计算
+    fn parse(content: &str) -> Self {
+        let field = |name: &str| {
+            content
+                .lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+                .and_then(|value| value.trim().trim_end_matches(" kB").parse::<u64>().ok())
+                .map_or(0, |kb| kb * 1024)
+        };
+        Self {
+            total: field("MemTotal"),
+            available: field("MemAvailable"),
+            free: field("MemFree"),
+            cached: field("Cached") + field("Buffers"),
+        }
+    }
+}
+
+// 依次执行开启的操作，返回换出内存的进程数和各项操作释放的字节数。
+// 丢弃页缓存和整理碎片要写 /proc/sys/vm，不是 root 时只提示跳过，不作为失败报告
+pub fn release(config: &MemoryConfig, is_admin: bool, sender: &EventSender) -> (usize, u64) {
+    report_meminfo("释放前", sender);
+    let mut bytes = 0;
+
+    let skip = |action: &str| {
+        sender.send(Event::Progress(format!("跳过{}: 需要 root 权限", action))).ok();
+    };
+    if config.drop_caches > 0 && !is_admin {
+        skip("丢弃页缓存");
+    } else if config.drop_caches > 0 {
+        bytes += measure("丢弃页缓存", sender, || drop_caches(config.drop_caches)).unwrap_or(0);
+    }
+    if config.compact_memory && !is_admin {
+        skip("整理内存碎片");
+    } else if config.compact_memory {
+        match compact_memory() {
+            Ok((before, after)) => {
+                sender.send(Event::MemoryCompacted { before, after }).ok();
+            }
+            Err(error) => {
+                sender.send(Event::MemoryActionFailed { action: "整理内存碎片".to_string(), error }).ok();
+            }
+        }
+    }
+
+    // 各进程的结果按释放量排序，只报告最多的 top_processes 个
+    let mut processes = Vec::new();
+    if !config.pageout_processes.is_empty() {
+        for (pid, name) in find_processes(&config.pageout_processes) {
+            let action = || format!("换出进程 {} ({})", name, pid);
+            match pageout(pid) {
+                Ok((before, after, error)) => {
+                    // 部分区域失败时报告错误，已经换出的部分仍然计入结果
+                    if let Some(error) = error {
+                        sender.send(Event::MemoryActionFailed { action: action(), error }).ok();
+                    }
+                    processes.push((pid, name, before, after));
+                }
+                Err(error) => {
+                    sender.send(Event::MemoryActionFailed { action: action(), error }).ok();
+                }
+            }
+        }
+    }
+    processes.sort_by_key(|&(_, _, before, after)| Reverse(before.saturating_sub(after)));
+    for (pid, name, before, after) in processes.iter().take(config.top_processes) {
+        sender.send(Event::ProcessTrimmed { pid: *pid, name: name.clone(), before: *before, after: *after }).ok();
+    }
+    bytes += processes.iter().map(|(_, _, before, after)| before.saturating_sub(*after)).sum::<u64>();
+
+    for cgroup in &config.reclaim_cgroups {
+        let action = format!("回收 cgroup {}", cgroup);
+        bytes += measure(&action, sender, || reclaim_cgroup(cgroup, config.reclaim_mb)).unwrap_or(0);
+    }
+
+    report_meminfo("释放后", sender);
+    (processes.len(), bytes)
+}
+
+fn report_meminfo(label: &'static str, sender: &EventSender) {
+    match MemInfo::read() {
+        Ok(MemInfo { total, available, free, cached }) => {
+            sender.send(Event::MemoryInfo { label, total, available, free, cached }).ok();
+        }
+        Err(error) => {
+            sender.send(Event::MemoryActionFailed { action: "读取 /proc/meminfo".to_string(), error }).ok();
+        }
+    }
+}
+
+// 执行一项操作并报告结果；操作返回 (操作前, 操作后) 的占用，释放量为两者之差
+fn measure(action: &str, sender: &EventSender, run: impl FnOnce() -> io::Result<(u64, u64)>) -> Option<u64> {
+    match run() {
+        Ok((before, after)) => {
+            let bytes = before.saturating_sub(after);
+            sender.send(Event::MemoryReclaimed { action: action.to_string(), bytes }).ok();
+            Some(bytes)
+        }
+        Err(error) => {
+            sender.send(Event::MemoryActionFailed { action: action.to_string(), error }).ok();
+            None
+        }
+    }
+}
+
+// 先 sync 让脏页可以被丢弃；释放量按 meminfo 中缓存的减少计算
+fn drop_caches(mode: u8) -> io::Result<(u64, u64)> {
+    let before = MemInfo::read()?.cached;
+    unsafe { libc::sync() };
+    fs::write("/proc/sys/vm/drop_caches", mode.to_string())?;
+    Ok((before, MemInfo::read()?.cached))
+}
+
+// 返回整理前后 COMPACT_ORDER 及以上阶的连续空闲内存
+fn compact_memory() -> io::Result<(u64, u64)> {
+    let before = contiguous_free()?;
+    fs::write("/proc/sys/vm/compact_memory", "1")?;
+    Ok((before, contiguous_free()?))
+}
+
+// /proc/buddyinfo 每行为 "Node 0, zone Normal" 加上各阶的空闲块数
+fn contiguous_free() -> io::Result<u64> {
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+    Ok(contiguous_pages(&fs::read_to_string("/proc/buddyinfo")?) * page_size)
+}
+
+fn contiguous_pages(content: &str) -> u64 {
+    content
+        .lines()
+        .flat_map(|line| {
+            line.split_whitespace()
+                .skip(4)
+                .enumerate()
+                .skip(COMPACT_ORDER)
+                .filter_map(|(order, count)| count.parse::<u64>().ok().map(|count| count << order))
+        })
+        .sum()
+}
+
+// 按进程名（/proc/<pid>/comm）查找进程，跳过自己
+fn find_processes(names: &[String]) -> Vec<(u32, String)> {
+    let own_pid = std::process::id();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return Vec::new();
+    };
+    let mut processes: Vec<(u32, String)> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok().filter(|&pid| pid != own_pid)?;
+            let comm = fs::read_to_string(entry.path().join("comm")).ok()?;
+            let comm = comm.trim_end();
+            names.iter().any(|name| name == comm).then(|| (pid, comm.to_string()))
+        })
+        .collect();
+    processes.sort();
+    processes
+}
+
+// 常驻内存按 /proc/<pid>/status 中的 VmRSS 计算
+fn resident(pid: u32) -> io::Result<u64> {
+    let status = fs::read_to_string(format!("/proc/{}/status", pid))?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.trim().trim_end_matches(" kB").parse::<u64>().ok())
+        .map(|kb| kb * 1024)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "进程没有用户态内存"))
+}
+
+// 对进程的所有映射区域发出 MADV_PAGEOUT，内核把其中的页写回文件或交换区后回收；
+// 需要 CAP_SYS_NICE，并且能够读取目标进程（同一用户或 root）。
+// 部分区域失败时仍然返回换出前后的占用，同时带回第一个错误；所有区域都失败时返回错误
+#[cfg(target_os = "linux")]
+fn pageout(pid: u32) -> io::Result<(u64, u64, Option<io::Error>)> {
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+    // 先打开 pidfd 再读取 /proc：读取之后进程仍然存活，说明进程号没有在这期间被其他进程重用
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let pidfd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+    let before = resident(pid)?;
+    let ranges = mapped_ranges(pid)?;
+    let alive = unsafe {
+        libc::syscall(libc::SYS_pidfd_send_signal, pidfd.as_raw_fd(), 0, std::ptr::null::<libc::siginfo_t>(), 0)
+    };
+    if alive < 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    let iovecs: Vec<libc::iovec> = ranges
+        .iter()
+        .map(|&(start, end)| libc::iovec { iov_base: start as *mut libc::c_void, iov_len: (end - start) as usize })
+        .collect();
+    // 每次调用最多 IOV_MAX 个区域；某一批失败（例如其中的区域已经被解除映射）时继续处理其余的
+    let mut succeeded = iovecs.is_empty();
+    let mut first_error = None;
+    for chunk in iovecs.chunks(1024) {
+        let result = unsafe {
+            libc::syscall(
+                libc::SYS_process_madvise,
+                pidfd.as_raw_fd(),
+                chunk.as_ptr(),
+                chunk.len(),
+                libc::MADV_PAGEOUT,
+                0,
+            )
+        };
+        if result < 0 {
+            first_error.get_or_insert_with(io::Error::last_os_error);
+        } else {
+            succeeded = true;
+        }
+    }
+    match first_error {
+        Some(error) if !succeeded => Err(error),
+        error => Ok((before, resident(pid)?, error)),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn pageout(_pid: u32) -> io::Result<(u64, u64, Option<io::Error>)> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "process_madvise 只在 Linux 上可用"))
+}
+
+// /proc/<pid>/maps 中的地址区间，跳过 [vsyscall]、[vvar] 等内核提供的特殊映射
+#[cfg(target_os = "linux")]
+fn mapped_ranges(pid: u32) -> io::Result<Vec<(u64, u64)>> {
+    Ok(parse_maps(&fs::read_to_string(format!("/proc/{}/maps", pid))?))
+}
+
+#[cfg(target_os = "linux")]
+fn parse_maps(maps: &str) -> Vec<(u64, u64)> {
+    maps.lines()
+        .filter(|line| !line.split_whitespace().nth(5).is_some_and(|name| name.starts_with("[v")))
+        .filter_map(|line| {
+            let (start, end) = line.split_whitespace().next()?.split_once('-')?;
+            Some((u64::from_str_radix(start, 16).ok()?, u64::from_str_radix(end, 16).ok()?))
+        })
+        .collect()
+}
+
+// 向 cgroup 请求回收 reclaim_mb；内核回收不到请求的数量时返回 EAGAIN，这时已回收的部分仍然有效
+fn reclaim_cgroup(cgroup: &str, reclaim_mb: u64) -> io::Result<(u64, u64)> {
+    let dir = Path::new(CGROUP_ROOT).join(cgroup.trim_start_matches('/'));
+    let current = || -> io::Result<u64> {
+        fs::read_to_string(dir.join("memory.current"))?
+            .trim()
+            .parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "无法解析 memory.current"))
+    };
+    let before = current()?;
+    if let Err(error) = fs::write(dir.join("memory.reclaim"), format!("{}M", reclaim_mb))
+        && error.raw_os_error() != Some(libc::EAGAIN)
+    {
+        return Err(error);
+    }
+    Ok((before, current()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn meminfo_fields() {
+        let info = MemInfo::parse(
+            "MemTotal:       16303428 kB\nMemFree:         1034560 kB\nMemAvailable:    9876544 kB\n\
+             Buffers:          204800 kB\nCached:          7340032 kB\nSwapCached:        10240 kB\n",
+        );
+        assert_eq!(info.total, 16303428 * 1024);
+        assert_eq!(info.free, 1034560 * 1024);
+        assert_eq!(info.available, 9876544 * 1024);
+        // SwapCached 不能当作 Cached
+        assert_eq!(info.cached, (7340032 + 204800) * 1024);
+        assert_eq!(MemInfo::parse("").total, 0);
+    }
+
+    #[test]
+    fn contiguous_pages_from_order_9() {
+        let buddyinfo = "Node 0, zone      DMA      1      1      1      0      2      1      1      0      1      1      3\n\
+                         Node 0, zone    DMA32      6      5      4      3      2      1      0      0      0      2      0\n\
+                         Node 0, zone   Normal    100     50     25     10      5      2      1      1      1      0      4\n";
+        // 9 阶 512 页、10 阶 1024 页
+        assert_eq!(contiguous_pages(buddyinfo), (1 + 2) * 512 + (3 + 4) * 1024);
+        assert_eq!(contiguous_pages("Node 0, zone Normal 1 2 3\n"), 0);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn maps_skip_kernel_mappings() {
+        let maps = "55d0c3a00000-55d0c3a21000 r--p 00000000 fd:01 1048602                    /usr/bin/cat\n\
+                    7ffd1c9e0000-7ffd1ca01000 rw-p 00000000 00:00 0                          [stack]\n\
+                    7f2a1c000000-7f2a1c021000 rw-p 00000000 00:00 0 \n\
+                    7ffd1cbd3000-7ffd1cbd7000 r--p 00000000 00:00 0                          [vvar]\n\
+                    ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsyscall]\n";
+        assert_eq!(
+            parse_maps(maps),
+            [(0x55d0c3a00000, 0x55d0c3a21000), (0x7ffd1c9e0000, 0x7ffd1ca01000), (0x7f2a1c000000, 0x7f2a1c021000)]
+        );
+    }
+
+    #[test]
+    fn privileged_actions_need_admin() {
+        let config = MemoryConfig { drop_caches: 3, compact_memory: true, ..MemoryConfig::default() };
+        let (sender, receiver) = mpsc::channel();
+        assert_eq!(release(&config, false, &sender), (0, 0));
+        drop(sender);
+        let events: Vec<Event> = receiver.into_iter().collect();
+        // 不作为失败报告，也没有执行
+        assert!(!events.iter().any(|event| matches!(
+            event,
+            Event::MemoryActionFailed { .. } | Event::MemoryReclaimed { .. } | Event::MemoryCompacted { .. }
+        )));
+        let hints: Vec<&str> = events
+            .iter()
+            .filter_map(|event| match event {
+                Event::Progress(message) => Some(message.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(hints, ["跳过丢弃页缓存: 需要 root 权限", "跳过整理内存碎片: 需要 root 权限"]);
+    }
+}
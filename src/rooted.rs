@@ -0,0 +1,266 @@
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io,
+    path::{Component, Path},
+};
+
+// 限定在清理目标根目录内的删除和移动操作：路径必须是根目录下的普通路径，
+// 中间任何一级是符号链接时都拒绝，链接本身只会作为链接删除，不会跟随到根目录之外
+
+pub fn remove_file(root: &Path, path: &Path) -> io::Result<()> {
+    imp::remove(root, path, false)
+}
+
+pub fn remove_dir(root: &Path, path: &Path) -> io::Result<()> {
+    imp::remove(root, path, true)
+}
+
+// 把根目录内的一项重命名到 dest（隔离区中的位置）
+pub fn rename(root: &Path, path: &Path, dest: &Path) -> io::Result<()> {
+    imp::rename(root, path, dest)
+}
+
+// 把 src（隔离区中的位置）重命名回根目录内的 path，用于把移入隔离区的项放回原处
+pub fn rename_back(root: &Path, src: &Path, path: &Path) -> io::Result<()> {
+    imp::rename_back(root, src, path)
+}
+
+// 以只读方式打开根目录内的文件，最后一级是符号链接时同样失败
+pub fn open_file(root: &Path, path: &Path) -> io::Result<File> {
+    imp::open(root, path, false)
+}
+
+// 在根目录内新建文件用于写入，文件已存在时失败
+pub fn create_file(root: &Path, path: &Path) -> io::Result<File> {
+    imp::open(root, path, true)
+}
+
+// 把根目录内的文件截断为 0 字节，保留文件本身（正在写入的进程持有的描述符继续有效），返回截断前的大小
+pub fn truncate(root: &Path, path: &Path) -> io::Result<u64> {
+    imp::truncate(root, path)
+}
+
+// 拆出相对于根目录的上级目录各级名称和最后一级名称
+fn split_relative<'a>(root: &Path, path: &'a Path) -> io::Result<(Vec<&'a OsStr>, &'a OsStr)> {
+    let escaped = || io::Error::new(io::ErrorKind::InvalidInput, format!("路径不在清理目标 {} 内", root.display()));
+    let relative = path.strip_prefix(root).map_err(|_| escaped())?;
+
+    let mut names = Vec::new();
+    for component in relative.components() {
+        match component {
+            Component::Normal(name) => names.push(name),
+            _ => return Err(escaped()),
+        }
+    }
+    let name = names.pop().ok_or_else(escaped)?;
+    Ok((names, name))
+}
+
+// Unix 上从根目录开始逐级 openat(O_NOFOLLOW)，再对最后打开的目录执行 unlinkat / renameat，
+// 扫描和删除之间目录被替换成符号链接时操作会失败，而不是删除链接指向的内容
+#[cfg(unix)]
+mod imp {
+    use std::{
+        ffi::{CString, OsStr},
+        io,
+        fs::File,
+        os::{
+            fd::{AsRawFd, FromRawFd, OwnedFd},
+            unix::ffi::OsStrExt,
+        },
+        path::Path,
+    };
+
+    fn c_string(name: &OsStr) -> io::Result<CString> {
+        CString::new(name.as_bytes()).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+    }
+
+    fn check(result: libc::c_int) -> io::Result<()> {
+        if result == -1 { Err(io::Error::last_os_error()) } else { Ok(()) }
+    }
+
+    fn open_parent(root: &Path, path: &Path) -> io::Result<(OwnedFd, CString)> {
+        let (parents, name) = super::split_relative(root, path)?;
+
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        let root = c_string(root.as_os_str())?;
+        let fd = unsafe { libc::open(root.as_ptr(), flags) };
+        check(fd)?;
+        let mut dir = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        for parent in parents {
+            let parent = c_string(parent)?;
+            let fd = unsafe { libc::openat(dir.as_raw_fd(), parent.as_ptr(), flags | libc::O_NOFOLLOW) };
+            check(fd)?;
+            dir = unsafe { OwnedFd::from_raw_fd(fd) };
+        }
+        Ok((dir, c_string(name)?))
+    }
+
+    pub fn remove(root: &Path, path: &Path, is_dir: bool) -> io::Result<()> {
+        let (dir, name) = open_parent(root, path)?;
+        let flags = if is_dir { libc::AT_REMOVEDIR } else { 0 };
+        check(unsafe { libc::unlinkat(dir.as_raw_fd(), name.as_ptr(), flags) })
+    }
+
+    pub fn rename(root: &Path, path: &Path, dest: &Path) -> io::Result<()> {
+        let (dir, name) = open_parent(root, path)?;
+        let dest = c_string(dest.as_os_str())?;
+        check(unsafe { libc::renameat(dir.as_raw_fd(), name.as_ptr(), libc::AT_FDCWD, dest.as_ptr()) })
+    }
+
+    pub fn rename_back(root: &Path, src: &Path, path: &Path) -> io::Result<()> {
+        let (dir, name) = open_parent(root, path)?;
+        let src = c_string(src.as_os_str())?;
+        check(unsafe { libc::renameat(libc::AT_FDCWD, src.as_ptr(), dir.as_raw_fd(), name.as_ptr()) })
+    }
+
+    // 新建的文件先只允许所有者读写，调用方写完后再设置需要的权限
+    pub fn open(root: &Path, path: &Path, create: bool) -> io::Result<File> {
+        let (dir, name) = open_parent(root, path)?;
+        let flags = if create {
+            libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL
+        } else {
+            libc::O_RDONLY
+        };
+        let fd = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags | libc::O_NOFOLLOW | libc::O_CLOEXEC, 0o600) };
+        check(fd)?;
+        Ok(File::from(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    // 不用 O_TRUNC 打开：先确认是普通文件，避免截断 FIFO 或设备时阻塞
+    pub fn truncate(root: &Path, path: &Path) -> io::Result<u64> {
+        let (dir, name) = open_parent(root, path)?;
+        let flags = libc::O_WRONLY | libc::O_NOFOLLOW | libc::O_NONBLOCK | libc::O_CLOEXEC;
+        let fd = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags) };
+        check(fd)?;
+        let file = File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        let metadata = file.metadata()?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "不是普通文件"));
+        }
+        file.set_len(0)?;
+        Ok(metadata.len())
+    }
+}
+
+// 其他平台没有 openat，操作前逐级检查上级目录不是符号链接或目录联接
+#[cfg(not(unix))]
+mod imp {
+    use std::{fs, io, path::Path};
+
+    fn check_parents(root: &Path, path: &Path) -> io::Result<()> {
+        let (parents, _) = super::split_relative(root, path)?;
+        let mut dir = root.to_path_buf();
+        for parent in parents {
+            dir.push(parent);
+            if fs::symlink_metadata(&dir)?.file_type().is_symlink() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} 是符号链接", dir.display())));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn remove(root: &Path, path: &Path, is_dir: bool) -> io::Result<()> {
+        check_parents(root, path)?;
+        if is_dir { fs::remove_dir(path) } else { fs::remove_file(path) }
+    }
+
+    pub fn rename(root: &Path, path: &Path, dest: &Path) -> io::Result<()> {
+        check_parents(root, path)?;
+        fs::rename(path, dest)
+    }
+
+    pub fn rename_back(root: &Path, src: &Path, path: &Path) -> io::Result<()> {
+        check_parents(root, path)?;
+        fs::rename(src, path)
+    }
+
+    // 最后一级同样不能是符号链接
+    fn check_path(root: &Path, path: &Path) -> io::Result<()> {
+        check_parents(root, path)?;
+        if fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink()) {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} 是符号链接", path.display())));
+        }
+        Ok(())
+    }
+
+    pub fn open(root: &Path, path: &Path, create: bool) -> io::Result<fs::File> {
+        check_path(root, path)?;
+        if create { fs::File::create_new(path) } else { fs::File::open(path) }
+    }
+
+    pub fn truncate(root: &Path, path: &Path) -> io::Result<u64> {
+        check_path(root, path)?;
+        let file = fs::OpenOptions::new().write(true).open(path)?;
+        let metadata = file.metadata()?;
+        if !metadata.is_file() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "不是普通文件"));
+        }
+        file.set_len(0)?;
+        Ok(metadata.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn refuses_paths_outside_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("root");
+        fs::create_dir(&root).unwrap();
+        let outside = temp.path().join("outside");
+        fs::write(&outside, "keep").unwrap();
+
+        assert!(remove_file(&root, &root.join("../outside")).is_err());
+        assert!(remove_file(&root, &outside).is_err());
+        assert!(remove_dir(&root, &root).is_err());
+        assert!(outside.exists());
+    }
+
+    #[test]
+    fn removes_and_renames_inside_root() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        fs::create_dir(root.join("dir")).unwrap();
+        fs::write(root.join("dir/a"), "a").unwrap();
+        fs::write(root.join("dir/b"), "b").unwrap();
+
+        remove_file(root, &root.join("dir/a")).unwrap();
+        rename(root, &root.join("dir/b"), &root.join("moved")).unwrap();
+        remove_dir(root, &root.join("dir")).unwrap();
+        assert_eq!(fs::read_to_string(root.join("moved")).unwrap(), "b");
+        assert!(!root.join("dir").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_intermediate_symlink() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path().join("root");
+        let outside = temp.path().join("outside");
+        fs::create_dir(&root).unwrap();
+        fs::create_dir(&outside).unwrap();
+        fs::write(outside.join("victim"), "keep").unwrap();
+        std::os::unix::fs::symlink(&outside, root.join("link")).unwrap();
+
+        let through_link = root.join("link/victim");
+        assert!(remove_file(&root, &through_link).is_err());
+        assert!(rename(&root, &through_link, &temp.path().join("moved")).is_err());
+        fs::write(temp.path().join("held"), "held").unwrap();
+        assert!(rename_back(&root, &temp.path().join("held"), &root.join("link/held")).is_err());
+        assert!(!outside.join("held").exists());
+        assert!(truncate(&root, &through_link).is_err());
+        assert!(open_file(&root, &through_link).is_err());
+        assert_eq!(fs::read_to_string(outside.join("victim")).unwrap(), "keep");
+
+        // 链接本身作为链接删除，不影响指向的目录
+        remove_file(&root, &root.join("link")).unwrap();
+        assert!(outside.join("victim").exists());
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    path::PathBuf,
+};
+
+use crate::{
+    Action, PlanEntry, Retention,
+    config::{AgeBy, LogScanConfig},
+    inuse::{OpenFiles, SkipReason},
+    is_old_enough,
+};
+
+// 按轮转组处理日志扫描找到的文件：同一目录下的 app.log、app.log.1、app.log.2.gz、app.log-20240101 属于同一组。
+// 每组保留最新的 keep_generations 个轮转文件，更旧的未压缩文件按年龄删除（开启 compress 时原地压缩），
+// 已压缩的超过 compressed_max_age_days 后删除。到期的当前日志仍被进程打开时截断，写入的进程可以继续写；
+// 没有被打开时和轮转文件一样压缩或删除
+
+const COMPRESSED_SUFFIXES: &[&str] = &[".gz", ".xz", ".bz2", ".zst"];
+
+struct Member {
+    entry: PlanEntry,
+    rotated: bool,
+    compressed: bool,
+}
+
+pub fn plan(candidates: Vec<PlanEntry>, log_scan: &LogScanConfig, open_files: &OpenFiles) -> Vec<PlanEntry> {
+    let mut families: BTreeMap<(PathBuf, String), Vec<Member>> = BTreeMap::new();
+    for entry in candidates {
+        let Some(name) = entry.path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+            continue;
+        };
+        let (base, rotated, compressed) = split_name(&name);
+        let dir = entry.path.parent().map(PathBuf::from).unwrap_or_default();
+        families.entry((dir, base)).or_default().push(Member { entry, rotated, compressed });
+    }
+
+    let retention = Retention {
+        min_age_days: log_scan.max_age_days,
+        age_by: AgeBy::Mtime,
+        min_size: log_scan.min_size_mb * 1024 * 1024,
+        keep_newest: 0,
+    };
+    let truncate_over = log_scan.truncate_over_mb * 1024 * 1024;
+    let archive_retention = Retention { min_age_days: log_scan.compressed_max_age_days, ..retention };
+    // 不压缩时到期即删除
+    let expired_action = if log_scan.compress { Action::Compress(log_scan.compress_format) } else { Action::Delete };
+
+    let mut plan = Vec::new();
+    for mut members in families.into_values() {
+        // 时间未知的文件视为最旧
+        members.sort_by_key(|member| Reverse(member.entry.time));
+        let mut generation = 0;
+
+        for Member { mut entry, rotated, compressed } in members {
+            if compressed {
+                generation += 1;
+                let expired = log_scan.compressed_max_age_days > 0 && is_old_enough(entry.time, &archive_retention);
+                if generation > log_scan.keep_generations && expired {
+                    plan.push(entry);
+                }
+                continue;
+            }
+
+            let old_enough = is_old_enough(entry.time, &retention);
+            if !rotated {
+                let in_use = matches!(open_files.check(&entry.path), Some(SkipReason::InUse(_)));
+                let expired = old_enough && entry.size >= retention.min_size;
+                if in_use && (expired || (truncate_over > 0 && entry.size >= truncate_over)) {
+                    entry.action = Action::Truncate;
+                    plan.push(entry);
+                } else if !in_use && expired {
+                    entry.action = expired_action;
+                    plan.push(entry);
+                }
+                continue;
+            }
+
+            generation += 1;
+            if generation <= log_scan.keep_generations || !old_enough {
+                continue;
+            }
+            // 压缩同样要等到期；空文件压缩没有意义，直接删除
+            if log_scan.compress && entry.size > 0 {
+                entry.action = expired_action;
+            }
+            plan.push(entry);
+        }
+    }
+    plan
+}
+
+// 拆出轮转组的基础名：先去掉压缩后缀，再去掉 .N、.old 或 dateext 的 -YYYYMMDD 后缀。
+// 返回 (基础名, 是否为轮转出来的文件, 是否已压缩)
+fn split_name(name: &str) -> (String, bool, bool) {
+    let (name, compressed) = match COMPRESSED_SUFFIXES.iter().find_map(|suffix| name.strip_suffix(suffix)) {
+        Some(stripped) if !stripped.is_empty() => (stripped, true),
+        _ => (name, false),
+    };
+
+    let base = name
+        .strip_suffix(".old")
+        .or_else(|| {
+            let (base, number) = name.rsplit_once('.')?;
+            (!number.is_empty() && number.bytes().all(|b| b.is_ascii_digit())).then_some(base)
+        })
+        .or_else(|| {
+            let (base, date) = name.rsplit_once('-')?;
+            (date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit())).then_some(base)
+        })
+        .filter(|base| !base.is_empty());
+
+    match base {
+        Some(base) => (base.to_string(), true, compressed),
+        None => (name.to_string(), compressed, compressed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::*;
+
+    fn family(base: &str, rotated: bool, compressed: bool) -> (String, bool, bool) {
+        (base.to_string(), rotated, compressed)
+    }
+
+    fn candidate(name: &str, days_old: u64, size: u64) -> PlanEntry {
+        PlanEntry {
+            target: "logs".to_string(),
+            root: PathBuf::from("/var/log"),
+            path: PathBuf::from("/var/log").join(name),
+            size,
+            is_dir: false,
+            rule: "logs".to_string(),
+            category: "日志文件",
+            time: Some(SystemTime::now() - Duration::from_secs(days_old * 24 * 3600)),
+            action: Action::Delete,
+        }
+    }
+
+    fn planned(candidates: Vec<PlanEntry>, log_scan: &LogScanConfig) -> Vec<(String, &'static str)> {
+        plan(candidates, log_scan, &OpenFiles::default())
+            .into_iter()
+            .map(|entry| (entry.path.file_name().unwrap().to_string_lossy().into_owned(), entry.action.verb()))
+            .collect()
+    }
+
+    #[test]
+    fn compression_waits_for_max_age() {
+        let log_scan = LogScanConfig { compress: true, keep_generations: 1, max_age_days: 30, ..LogScanConfig::default() };
+        let candidates = vec![
+            candidate("app.log.1", 1, 100),
+            candidate("app.log.2", 2, 100),
+            candidate("app.log.3", 40, 100),
+            candidate("app.log.4", 50, 0),
+        ];
+        // 超过保留代数但未到期的 app.log.2 不压缩，到期的空文件直接删除
+        let compress = Action::Compress(log_scan.compress_format).verb();
+        assert_eq!(planned(candidates, &log_scan), [
+            ("app.log.3".to_string(), compress),
+            ("app.log.4".to_string(), Action::Delete.verb()),
+        ]);
+    }
+
+    #[test]
+    fn split_rotation_suffixes() {
+        assert_eq!(split_name("app.log"), family("app.log", false, false));
+        assert_eq!(split_name("app.log.1"), family("app.log", true, false));
+        assert_eq!(split_name("app.log.12"), family("app.log", true, false));
+        assert_eq!(split_name("app.log.2.gz"), family("app.log", true, true));
+        assert_eq!(split_name("app.log.old"), family("app.log", true, false));
+        assert_eq!(split_name("app.log-20240101"), family("app.log", true, false));
+        assert_eq!(split_name("app.log-20240101.zst"), family("app.log", true, true));
+        assert_eq!(split_name("syslog.1"), family("syslog", true, false));
+    }
+
+    #[test]
+    fn split_non_rotated_names() {
+        // 只压缩没有编号的文件也算轮转文件
+        assert_eq!(split_name("app.log.gz"), family("app.log", true, true));
+        assert_eq!(split_name("app-2024.log"), family("app-2024.log", false, false));
+        assert_eq!(split_name("app.log-2024"), family("app.log-2024", false, false));
+        assert_eq!(split_name(".gz"), family(".gz", false, false));
+        assert_eq!(split_name(".1"), family(".1", false, false));
+    }
+}
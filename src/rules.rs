@@ -0,0 +1,340 @@
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::Regex;
+use serde::Deserialize;
+
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::config::{RuleConfig, RuleSet};
+
+// 内置规则集，名称会显示在计划和隔离清单中
+const ALL: &[&str] = &["*"];
+const DOWNLOAD_TEMP: &[&str] = &[
+    "*.crdownload", "*.part", "*.partial", "*.download", "*.!ut", "*.bc!", "*.crx",
+    "~*", "*tmp*", "*temp*", "*cache*",
+];
+const FIREFOX_CACHE: &[&str] = &["*cache*", "*temp*", "*.tmp"];
+const RECENT_LNK: &[&str] = &["*.lnk"];
+const TEMP_EXTENSIONS: &[&str] = &["*.tmp", "*.temp", "*.cache", "*.log", "*.dmp", "*.mdmp"];
+const LOG_FILES: &[&str] = &[
+    "*.log", "*.log.*", "*log*.txt", "*log*.out", "*log*.err", "re:\\.log-[0-9]{8}(\\.(gz|xz|bz2|zst))?$",
+];
+// logrotate 轮转出来的旧文件：数字后缀、压缩文件、.old 和 dateext 的日期后缀
+const ROTATED_LOGS: &[&str] = &[
+    "*.[0-9]", "*.[0-9][0-9]", "*.gz", "*.xz", "*.bz2", "*.zst", "*.old", "re:-[0-9]{8}$",
+];
+
+// 一组清理规则。exclude 中的模式优先于所有 include，命中的目录整个跳过；
+// include 按顺序匹配，第一个命中的规则决定是否清理（再检查它的年龄和大小条件）。
+// 命中 include 的目录中的所有内容都归这条规则处理，与 gitignore 的语义一致
+#[derive(Deserialize, Clone, Default)]
+#[serde(try_from = "Vec<RuleConfig>")]
+pub struct RuleList {
+    includes: Vec<Rule>,
+    excludes: PatternSet,
+}
+
+#[derive(Clone)]
+pub struct Rule {
+    name: String,
+    patterns: PatternSet,
+    min_age_days: u64,
+    min_size: u64,
+}
+
+// 一组模式编译成的匹配器，通配符合并为 GlobSet，排除列表很长时也只需匹配一次
+#[derive(Clone, Default)]
+struct PatternSet {
+    globs: GlobSet,
+    dir_globs: GlobSet, // 以 / 结尾、只匹配目录的模式
+    regexes: Vec<Regex>,
+}
+
+pub enum Verdict<'a> {
+    Excluded,
+    Included(&'a Rule),
+    Unmatched,
+}
+
+impl TryFrom<Vec<RuleConfig>> for RuleList {
+    type Error = String;
+
+    fn try_from(configs: Vec<RuleConfig>) -> Result<Self, String> {
+        let mut includes = Vec::new();
+        let mut excludes = Vec::new();
+        for (index, config) in configs.into_iter().enumerate() {
+            if config.include.is_empty() && config.exclude.is_empty() {
+                return Err(format!("rules[{}] 至少需要 include 或 exclude", index));
+            }
+            if config.include.is_empty() && (config.min_age_days > 0 || config.min_size_kb > 0) {
+                return Err(format!("rules[{}] 的 min_age_days / min_size_kb 只能与 include 一起使用", index));
+            }
+
+            excludes.extend(config.exclude);
+            if !config.include.is_empty() {
+                includes.push(Rule {
+                    patterns: PatternSet::build(&config.include)?,
+                    name: config.name.unwrap_or_else(|| config.include.join(" ")),
+                    min_age_days: config.min_age_days,
+                    min_size: config.min_size_kb * 1024,
+                });
+            }
+        }
+        Ok(Self { includes, excludes: PatternSet::build(&excludes)? })
+    }
+}
+
+impl RuleList {
+    // 配置中 rule 选项对应的内置规则；auto 按目标目录名选择
+    pub fn preset(rule_set: RuleSet, dir: &Path) -> Self {
+        let dir_str = dir.to_string_lossy().to_lowercase();
+        let rules: &[(&str, &[&str])] = match rule_set {
+            RuleSet::All => &[("all", ALL)],
+            RuleSet::Downloads => &[("downloads-temp", DOWNLOAD_TEMP)],
+            RuleSet::Firefox => &[("firefox-cache", FIREFOX_CACHE)],
+            RuleSet::Recent => &[("recent-lnk", RECENT_LNK)],
+            RuleSet::TempFiles => &[("temp-extension", TEMP_EXTENSIONS)],
+            RuleSet::Auto if dir_str.contains("downloads") => &[("downloads-temp", DOWNLOAD_TEMP)],
+            RuleSet::Auto if dir_str.contains("firefox") && dir_str.contains("profiles") => {
+                &[("firefox-cache", FIREFOX_CACHE)]
+            }
+            RuleSet::Auto if dir_str.contains("recent") => &[("recent-lnk", RECENT_LNK)],
+            RuleSet::Auto if dir_str.contains("cache") || dir_str.contains("temp") => &[("cache-temp-dir", ALL)],
+            RuleSet::Auto if ["crashdumps", "reportqueue", "minidump", "livekernelreports"]
+                .iter()
+                .any(|name| dir_str.contains(name)) => &[("crash-report", ALL)],
+            // 其他系统临时文件夹默认清理所有内容
+            RuleSet::Auto => &[("temp-extension", TEMP_EXTENSIONS), ("system-temp-dir", ALL)],
+        };
+        Self::builtin(rules)
+    }
+
+    // 磁盘日志扫描使用的规则
+    pub fn logs() -> Self {
+        Self::builtin(&[("log-age-size", LOG_FILES)])
+    }
+
+    // /var/log 只清理轮转出来的旧文件，不碰正在写入的日志；journal 目录由专门的目标处理
+    pub fn rotated_logs() -> Self {
+        let mut list = Self::builtin(&[("rotated-log", ROTATED_LOGS)]);
+        list.excludes = PatternSet::build(&["/journal/"]).expect("内置规则无效");
+        list
+    }
+
+    pub fn builtin(rules: &[(&str, &[&str])]) -> Self {
+        let includes = rules
+            .iter()
+            .map(|(name, patterns)| Rule {
+                name: name.to_string(),
+                patterns: PatternSet::build(patterns).expect("内置规则无效"),
+                min_age_days: 0,
+                min_size: 0,
+            })
+            .collect();
+        Self { includes, excludes: PatternSet::default() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.includes.is_empty() && self.excludes.is_empty()
+    }
+
+    // relative 为相对于目标目录的路径
+    pub fn check(&self, relative: &Path, is_dir: bool) -> Verdict<'_> {
+        if self.excludes.matches(relative, is_dir) {
+            return Verdict::Excluded;
+        }
+        match self.includes.iter().find(|rule| rule.patterns.matches(relative, is_dir)) {
+            Some(rule) => Verdict::Included(rule),
+            None => Verdict::Unmatched,
+        }
+    }
+}
+
+impl Rule {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // 规则自身的年龄和大小条件；目录不检查大小
+    pub fn allows(&self, size: u64, time: Option<SystemTime>, is_dir: bool) -> bool {
+        let min_age = Duration::from_secs(self.min_age_days * 24 * 3600);
+        let old_enough = self.min_age_days == 0
+            || time.and_then(|time| SystemTime::now().duration_since(time).ok()).is_some_and(|age| age >= min_age);
+        old_enough && (is_dir || size >= self.min_size)
+    }
+}
+
+impl PatternSet {
+    // 以 re: 开头的是正则表达式，匹配以 / 分隔的相对路径；
+    // 其余按 gitignore 的写法：不含 / 的模式匹配任意层级的名称，以 / 开头或中间含 / 的模式从目标目录开始匹配，
+    // 以 / 结尾的模式只匹配目录。通配符不区分大小写
+    fn build(patterns: &[impl AsRef<str>]) -> Result<Self, String> {
+        let mut globs = GlobSetBuilder::new();
+        let mut dir_globs = GlobSetBuilder::new();
+        let mut regexes = Vec::new();
+
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            if let Some(regex) = pattern.strip_prefix("re:") {
+                regexes.push(Regex::new(regex).map_err(|err| format!("无效的正则表达式 {}: {}", regex, err))?);
+                continue;
+            }
+
+            let (glob, dir_only) = match pattern.strip_suffix('/') {
+                Some(glob) => (glob, true),
+                None => (pattern, false),
+            };
+            let glob = match glob.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if glob.contains('/') => glob.to_string(),
+                None => format!("**/{}", glob),
+            };
+            let glob = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .case_insensitive(true)
+                .build()
+                .map_err(|err| format!("无效的通配符 {}: {}", pattern, err))?;
+            if dir_only { dir_globs.add(glob) } else { globs.add(glob) };
+        }
+
+        let build = |builder: GlobSetBuilder| builder.build().map_err(|err| err.to_string());
+        Ok(Self { globs: build(globs)?, dir_globs: build(dir_globs)?, regexes })
+    }
+
+    fn is_empty(&self) -> bool {
+        self.globs.is_empty() && self.dir_globs.is_empty() && self.regexes.is_empty()
+    }
+
+    fn matches(&self, relative: &Path, is_dir: bool) -> bool {
+        self.globs.is_match(relative)
+            || (is_dir && self.dir_globs.is_match(relative))
+            || (!self.regexes.is_empty() && {
+                let relative = relative.to_string_lossy().replace('\\', "/");
+                self.regexes.iter().any(|regex| regex.is_match(&relative))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(name: &str, include: &[&str], exclude: &[&str]) -> RuleConfig {
+        RuleConfig {
+            name: Some(name.to_string()),
+            include: include.iter().map(|pattern| pattern.to_string()).collect(),
+            exclude: exclude.iter().map(|pattern| pattern.to_string()).collect(),
+            min_age_days: 0,
+            min_size_kb: 0,
+        }
+    }
+
+    fn rules(configs: Vec<RuleConfig>) -> RuleList {
+        RuleList::try_from(configs).unwrap()
+    }
+
+    // 命中的规则名，排除时为 "excluded"，没有命中时为 "unmatched"
+    fn verdict(list: &RuleList, path: &str, is_dir: bool) -> String {
+        match list.check(Path::new(path), is_dir) {
+            Verdict::Excluded => "excluded".to_string(),
+            Verdict::Included(rule) => rule.name().to_string(),
+            Verdict::Unmatched => "unmatched".to_string(),
+        }
+    }
+
+    #[test]
+    fn exclude_wins_over_any_include() {
+        // 排除写在后面的规则中，仍然优先于前面的 include
+        let list = rules(vec![rule("logs", &["*.log"], &[]), rule("keep", &[], &["keep.log", "important/"])]);
+        assert_eq!(verdict(&list, "a/b.log", false), "logs");
+        assert_eq!(verdict(&list, "a/keep.log", false), "excluded");
+        assert_eq!(verdict(&list, "x/important", true), "excluded");
+        assert_eq!(verdict(&list, "a/b.txt", false), "unmatched");
+    }
+
+    #[test]
+    fn first_matching_include_decides() {
+        let list = rules(vec![rule("first", &["*.tmp"], &[]), rule("second", &["*"], &[])]);
+        assert_eq!(verdict(&list, "a.tmp", false), "first");
+        assert_eq!(verdict(&list, "a.txt", false), "second");
+    }
+
+    #[test]
+    fn anchoring() {
+        let list = rules(vec![
+            rule("root", &["/build/*.o"], &[]),
+            rule("nested", &["src/*.tmp"], &[]),
+            rule("anywhere", &["cache"], &[]),
+        ]);
+        assert_eq!(verdict(&list, "build/a.o", false), "root");
+        assert_eq!(verdict(&list, "x/build/a.o", false), "unmatched");
+        // * 不跨越 /
+        assert_eq!(verdict(&list, "build/sub/a.o", false), "unmatched");
+        assert_eq!(verdict(&list, "src/a.tmp", false), "nested");
+        assert_eq!(verdict(&list, "x/src/a.tmp", false), "unmatched");
+        assert_eq!(verdict(&list, "a/b/cache", true), "anywhere");
+        assert_eq!(verdict(&list, "CACHE", false), "anywhere");
+    }
+
+    #[test]
+    fn dir_only_patterns() {
+        let list = rules(vec![rule("tmp-dir", &["tmp/"], &[]), rule("anchored-dir", &["/out/"], &[])]);
+        assert_eq!(verdict(&list, "a/tmp", true), "tmp-dir");
+        assert_eq!(verdict(&list, "a/tmp", false), "unmatched");
+        assert_eq!(verdict(&list, "out", true), "anchored-dir");
+        assert_eq!(verdict(&list, "a/out", true), "unmatched");
+    }
+
+    #[test]
+    fn regex_patterns() {
+        let list = rules(vec![rule("bak", &["re:^logs/.*\\.bak$"], &["re:keep"])]);
+        assert_eq!(verdict(&list, "logs/a/b.bak", false), "bak");
+        assert_eq!(verdict(&list, "other/logs/b.bak", false), "unmatched");
+        assert_eq!(verdict(&list, "logs/keep.bak", false), "excluded");
+    }
+
+    #[test]
+    fn log_rules_match_dateext_names() {
+        let logs = RuleList::logs();
+        for name in ["app.log", "app.log.1", "app.log.2.gz", "app.log-20240101", "app.log-20240101.gz", "buildlog.txt"] {
+            assert_ne!(verdict(&logs, name, false), "unmatched", "{}", name);
+        }
+        assert_eq!(verdict(&logs, "app.log-2024", false), "unmatched");
+
+        let rotated = RuleList::rotated_logs();
+        for name in ["syslog.1", "syslog.2.gz", "messages-20240101", "dpkg.log.old"] {
+            assert_eq!(verdict(&rotated, name, false), "rotated-log", "{}", name);
+        }
+        assert_eq!(verdict(&rotated, "syslog", false), "unmatched");
+        assert_eq!(verdict(&rotated, "journal", true), "excluded");
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert!(RuleList::try_from(vec![rule("empty", &[], &[])]).is_err());
+        assert!(RuleList::try_from(vec![rule("regex", &["re:("], &[])]).is_err());
+        let mut aged_exclude = rule("aged", &[], &["*.keep"]);
+        aged_exclude.min_age_days = 1;
+        assert!(RuleList::try_from(vec![aged_exclude]).is_err());
+    }
+
+    #[test]
+    fn rule_age_and_size() {
+        let mut config = rule("old-big", &["*"], &[]);
+        config.min_age_days = 2;
+        config.min_size_kb = 1;
+        let list = rules(vec![config]);
+        let Verdict::Included(rule) = list.check(Path::new("a"), false) else {
+            panic!("应命中规则");
+        };
+        let old = SystemTime::now() - Duration::from_secs(3 * 24 * 3600);
+        assert!(rule.allows(1024, Some(old), false));
+        assert!(!rule.allows(1023, Some(old), false));
+        assert!(!rule.allows(1024, Some(SystemTime::now()), false));
+        assert!(!rule.allows(1024, None, false));
+        // 目录不检查大小
+        assert!(rule.allows(0, Some(old), true));
+    }
+}
@@ -0,0 +1,82 @@
+use std::path::{Path, PathBuf};
+
+// 一个受清理影响的文件系统，记录清理前后的可用空间
+pub struct FilesystemSpace {
+    pub root: PathBuf,
+    pub before: Option<u64>,
+    pub after: Option<u64>,
+}
+
+impl FilesystemSpace {
+    // 按文件系统去重，记录清理前的可用空间
+    pub fn snapshot<'a>(paths: impl Iterator<Item = &'a Path>) -> Vec<Self> {
+        let mut filesystems: Vec<Self> = Vec::new();
+        for path in paths {
+            let Some(root) = filesystem_root(path) else {
+                continue;
+            };
+            if filesystems.iter().all(|fs| fs.root != root) {
+                let before = available_space(&root);
+                filesystems.push(Self { root, before, after: None });
+            }
+        }
+        filesystems
+    }
+
+    pub fn refresh(filesystems: &mut [Self]) {
+        for fs in filesystems {
+            fs.after = available_space(&fs.root);
+        }
+    }
+}
+
+// 找到路径所在文件系统的挂载点（Windows 上为盘符根目录）
+#[cfg(unix)]
+pub fn filesystem_root(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::MetadataExt;
+
+    let existing = path.ancestors().find(|dir| dir.symlink_metadata().is_ok())?;
+    let dev = existing.symlink_metadata().ok()?.dev();
+    let mut root = existing;
+    while let Some(parent) = root.parent() {
+        match parent.metadata() {
+            Ok(metadata) if metadata.dev() == dev => root = parent,
+            _ => break,
+        }
+    }
+    Some(root.to_path_buf())
+}
+
+#[cfg(windows)]
+pub fn filesystem_root(path: &Path) -> Option<PathBuf> {
+    path.ancestors().last().map(Path::to_path_buf)
+}
+
+// statvfs 的字段宽度因平台而异
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)]
+pub fn available_space(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = std::mem::MaybeUninit::<libc::statvfs>::uninit();
+    let result = unsafe { libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) };
+    if result != 0 {
+        return None;
+    }
+    let stat = unsafe { stat.assume_init() };
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+pub fn available_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    let result = unsafe {
+        GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+    (result != 0).then_some(available)
+}
@@ -0,0 +1,184 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    Target,
+    config::{SweepConfig, SweepMode},
+    rules::RuleList,
+};
+
+// Rust 项目的 target/ 目录：在 project_roots 下查找带 Cargo.toml 的目录，
+// 其 target/ 中有 cargo 写入的 CACHEDIR.TAG 或 .rustc_info.json 时才认为是构建输出
+pub struct RustProject {
+    pub root: PathBuf,
+    pub target_dir: PathBuf,
+    pub last_build: Option<SystemTime>,
+}
+
+// 这些目录中不会有需要考虑的项目
+const SKIPPED_DIR_NAMES: &[&str] = &["node_modules", "target"];
+
+pub fn find_projects(config: &SweepConfig) -> Vec<RustProject> {
+    let mut projects = Vec::new();
+    for root in &config.project_roots {
+        find_in(root, 0, config.max_depth, &mut projects);
+    }
+    projects
+}
+
+// 不跟随符号链接，跳过隐藏目录；工作区中的成员包和嵌套的其他工作区照常查找
+fn find_in(dir: &Path, depth: usize, max_depth: usize, projects: &mut Vec<RustProject>) {
+    let target_dir = dir.join("target");
+    if dir.join("Cargo.toml").is_file() && is_cargo_target_dir(&target_dir) {
+        projects.push(RustProject { root: dir.to_path_buf(), last_build: last_build(&target_dir), target_dir });
+    }
+    if depth >= max_depth {
+        return;
+    }
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+            && !name.starts_with('.')
+            && !SKIPPED_DIR_NAMES.contains(&name.as_ref())
+        {
+            find_in(&entry.path(), depth + 1, max_depth, projects);
+        }
+    }
+}
+
+fn is_cargo_target_dir(dir: &Path) -> bool {
+    fs::symlink_metadata(dir).is_ok_and(|metadata| metadata.is_dir())
+        && (dir.join("CACHEDIR.TAG").is_file() || dir.join(".rustc_info.json").is_file())
+}
+
+// 上次构建时间：target/ 及其下两层（如 debug/.fingerprint、debug/deps）中最新的修改时间
+fn last_build(target_dir: &Path) -> Option<SystemTime> {
+    fn newest(dir: &Path, depth: usize) -> Option<SystemTime> {
+        let modified = fs::symlink_metadata(dir).and_then(|metadata| metadata.modified()).ok();
+        if depth == 0 {
+            return modified;
+        }
+        let children = fs::read_dir(dir).into_iter().flatten().filter_map(Result::ok);
+        children
+            .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+            .filter_map(|entry| newest(&entry.path(), depth - 1))
+            .chain(modified)
+            .max()
+    }
+    newest(target_dir, 2)
+}
+
+impl RustProject {
+    // 超过 max_idle_days 天没有构建；无法取得时间的项目不清理
+    pub fn is_idle(&self, max_idle_days: u64) -> bool {
+        let max_idle = Duration::from_secs(max_idle_days * 24 * 3600);
+        self.last_build
+            .and_then(|time| SystemTime::now().duration_since(time).ok())
+            .is_some_and(|idle| idle >= max_idle)
+    }
+
+    pub fn idle_days(&self) -> Option<u64> {
+        let idle = SystemTime::now().duration_since(self.last_build?).ok()?;
+        Some(idle.as_secs() / (24 * 3600))
+    }
+
+    // 清理目标以项目目录命名，计划和汇总按项目分别显示
+    pub fn target(&self, mode: SweepMode) -> Target {
+        let rules: &[(&str, &[&str])] = match mode {
+            SweepMode::All => &[("rust-target", &["*"])],
+            // 各 profile 的 debug 目录，交叉编译时在 target/<triple>/debug
+            SweepMode::Debug => &[("rust-debug", &["/debug/", "/*/debug/"])],
+            SweepMode::Incremental => &[("rust-incremental", &["/*/incremental/", "/*/*/incremental/"])],
+        };
+        Target::new(&self.root.to_string_lossy(), &self.target_dir)
+            .with_rules(RuleList::builtin(rules))
+            .with_category("构建产物")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 3600);
+
+    fn project(dir: &Path, tag: &str) {
+        fs::create_dir_all(dir.join("target")).unwrap();
+        fs::write(dir.join("Cargo.toml"), "[package]\n").unwrap();
+        fs::write(dir.join("target").join(tag), "").unwrap();
+    }
+
+    fn set_age(dir: &Path, days: u32) {
+        fs::File::open(dir).unwrap().set_modified(SystemTime::now() - DAY * days).unwrap();
+    }
+
+    #[test]
+    fn finds_cargo_projects_with_build_output() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        project(&root.join("a"), "CACHEDIR.TAG");
+        project(&root.join("ws"), ".rustc_info.json");
+        project(&root.join("ws/member"), "CACHEDIR.TAG");
+        // target/ 不是 cargo 的构建输出、没有 Cargo.toml、隐藏目录和 node_modules 中的项目都不算
+        project(&root.join("untagged"), "other");
+        fs::create_dir_all(root.join("no-manifest/target")).unwrap();
+        fs::write(root.join("no-manifest/target/CACHEDIR.TAG"), "").unwrap();
+        project(&root.join(".hidden/p"), "CACHEDIR.TAG");
+        project(&root.join("node_modules/p"), "CACHEDIR.TAG");
+        // 超过 max_depth 的不查找
+        project(&root.join("x/y/z/deep"), "CACHEDIR.TAG");
+
+        let config = SweepConfig { project_roots: vec![root.to_path_buf()], max_depth: 3, ..SweepConfig::default() };
+        let mut found: Vec<PathBuf> = find_projects(&config).into_iter().map(|project| project.root).collect();
+        found.sort();
+        assert_eq!(found, [root.join("a"), root.join("ws"), root.join("ws/member")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinked_target_is_not_build_output() {
+        let temp = tempfile::tempdir().unwrap();
+        let elsewhere = temp.path().join("elsewhere");
+        fs::create_dir(&elsewhere).unwrap();
+        fs::write(elsewhere.join("CACHEDIR.TAG"), "").unwrap();
+        fs::create_dir(temp.path().join("p")).unwrap();
+        fs::write(temp.path().join("p/Cargo.toml"), "").unwrap();
+        std::os::unix::fs::symlink(&elsewhere, temp.path().join("p/target")).unwrap();
+        assert!(!is_cargo_target_dir(&temp.path().join("p/target")));
+    }
+
+    #[test]
+    fn last_build_looks_two_levels_deep() {
+        let temp = tempfile::tempdir().unwrap();
+        let target = temp.path().join("target");
+        fs::create_dir_all(target.join("debug/deps")).unwrap();
+        fs::create_dir_all(target.join("debug/build/foo")).unwrap();
+        for dir in ["debug/build/foo", "debug/build", "debug", ""] {
+            set_age(&target.join(dir), 40);
+        }
+        // 第三层的修改不算作构建
+        set_age(&target.join("debug/build/foo"), 1);
+        set_age(&target.join("debug/deps"), 40);
+
+        let project = |last_build| RustProject { root: temp.path().to_path_buf(), target_dir: target.clone(), last_build };
+        let idle = project(last_build(&target));
+        assert_eq!(idle.idle_days(), Some(40));
+        assert!(idle.is_idle(30));
+
+        set_age(&target.join("debug/deps"), 2);
+        let recent = project(last_build(&target));
+        assert_eq!(recent.idle_days(), Some(2));
+        assert!(!recent.is_idle(30));
+
+        // 无法取得构建时间的项目不清理
+        assert!(!project(None).is_idle(0));
+    }
+}
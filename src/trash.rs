@@ -0,0 +1,312 @@
+use std::{
+    fs, io,
+    path::{Component, Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    Retention,
+    config::TrashConfig,
+    event::{Event, EventSender},
+    is_old_enough,
+    mounts::Mounts,
+    tree_size,
+};
+
+// freedesktop.org 回收站规范：每个回收站目录下 files/ 存放删除的项，info/<名称>.trashinfo 记录原路径和 DeletionDate，
+// 可选的 directorysizes 缓存 files/ 中各目录的大小。用户主目录的回收站在 $XDG_DATA_HOME/Trash，
+// 其他挂载点上的在 <挂载点>/.Trash/$UID（.Trash 必须是设置了粘滞位的目录）或 <挂载点>/.Trash-$UID
+
+const INFO_SUFFIX: &str = ".trashinfo";
+const DIRECTORY_SIZES: &str = "directorysizes";
+
+// 清理所有回收站中删除时间超过 min_age_days 的项，返回释放的字节数；单项删除失败时发送 DeleteFailed，
+// 某个回收站无法读取时发送 RecycleBinFailed，都继续处理其余的；没有找到回收站时返回 0。
+// data_home 为 $XDG_DATA_HOME，用户主目录的回收站位于其中
+pub fn purge(config: &TrashConfig, data_home: Option<&Path>, mounts: &Mounts, sender: &EventSender) -> u64 {
+    let retention = Retention { min_age_days: config.min_age_days, ..Retention::default() };
+    let mut bytes = 0;
+    for trash in trash_dirs(data_home, mounts) {
+        match purge_dir(&trash, &retention, sender) {
+            Ok(purged) => bytes += purged,
+            Err(error) => {
+                let error = io::Error::new(error.kind(), format!("{}: {}", trash.display(), error));
+                sender.send(Event::RecycleBinFailed(error)).ok();
+            }
+        }
+    }
+    bytes
+}
+
+fn trash_dirs(data_home: Option<&Path>, mounts: &Mounts) -> Vec<PathBuf> {
+    let uid = unsafe { libc::getuid() };
+    let mut dirs: Vec<PathBuf> = data_home.map(|data_home| data_home.join("Trash")).into_iter().collect();
+
+    for mount_point in mounts.mount_points() {
+        let shared = mount_point.join(".Trash");
+        if is_sticky_dir(&shared) {
+            dirs.push(shared.join(uid.to_string()));
+        }
+        dirs.push(mount_point.join(format!(".Trash-{}", uid)));
+    }
+
+    // 回收站本身不能是符号链接；同一目录可能经由不同的挂载点出现多次
+    let mut seen = Vec::new();
+    dirs.retain(|dir| {
+        let is_dir = fs::symlink_metadata(dir).is_ok_and(|metadata| metadata.is_dir());
+        match dir.canonicalize() {
+            Ok(canonical) if is_dir && !seen.contains(&canonical) => {
+                seen.push(canonical);
+                true
+            }
+            _ => false,
+        }
+    });
+    dirs
+}
+
+fn purge_dir(trash: &Path, retention: &Retention, sender: &EventSender) -> io::Result<u64> {
+    let info_dir = trash.join("info");
+    let files_dir = trash.join("files");
+    let entries = match fs::read_dir(&info_dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => return Err(error),
+    };
+
+    let mut bytes = 0;
+    let mut purged_dir = false;
+    for entry in entries.filter_map(Result::ok) {
+        let info_name = entry.file_name();
+        let Some(name) = info_name.to_str().and_then(|name| name.strip_suffix(INFO_SUFFIX)) else {
+            continue;
+        };
+        // ".trashinfo"、"..trashinfo"、"...trashinfo" 之类的名字会指向 files/ 本身或回收站目录，只接受单个普通文件名
+        let mut components = Path::new(name).components();
+        if !matches!((components.next(), components.next()), (Some(Component::Normal(_)), None)) {
+            continue;
+        }
+        let info_path = entry.path();
+        let deleted_at = fs::read_to_string(&info_path).ok().as_deref().and_then(deletion_date);
+        if !is_old_enough(deleted_at, retention) {
+            continue;
+        }
+
+        // 先删除项本身再删除 .trashinfo，中途失败时信息文件仍然指向剩下的内容
+        let path = files_dir.join(name);
+        let (size, result) = match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => {
+                purged_dir = true;
+                (tree_size(&path), fs::remove_dir_all(&path))
+            }
+            Ok(metadata) => (metadata.len(), fs::remove_file(&path)),
+            // 没有对应内容的信息文件直接删除
+            Err(error) if error.kind() == io::ErrorKind::NotFound => (0, Ok(())),
+            Err(error) => (0, Err(error)),
+        };
+        match result.and_then(|()| fs::remove_file(&info_path)) {
+            Ok(()) => bytes += size,
+            Err(error) => {
+                sender.send(Event::DeleteFailed { path, error }).ok();
+            }
+        }
+    }
+
+    if purged_dir {
+        update_directory_sizes(trash, &files_dir);
+    }
+    Ok(bytes)
+}
+
+// 去掉 directorysizes 中已经不存在的目录；按规范写入临时文件后重命名替换
+fn update_directory_sizes(trash: &Path, files_dir: &Path) {
+    let path = trash.join(DIRECTORY_SIZES);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return;
+    };
+    // 每行为 "大小 修改时间 百分号编码的目录名"
+    let kept: Vec<&str> = content
+        .lines()
+        .filter(|line| {
+            line.splitn(3, ' ')
+                .nth(2)
+                .and_then(percent_decode)
+                .is_some_and(|name| !name.contains('/') && files_dir.join(name).exists())
+        })
+        .collect();
+    if kept.len() == content.lines().count() {
+        return;
+    }
+
+    let temp = trash.join(format!("{}.{}", DIRECTORY_SIZES, std::process::id()));
+    let mut updated = kept.join("\n");
+    if !updated.is_empty() {
+        updated.push('\n');
+    }
+    if fs::write(&temp, updated).and_then(|()| fs::rename(&temp, &path)).is_err() {
+        fs::remove_file(&temp).ok();
+    }
+}
+
+// [Trash Info] 段中的 DeletionDate=YYYY-MM-DDThh:mm:ss，为没有时区的本地时间
+fn deletion_date(info: &str) -> Option<SystemTime> {
+    let value = info
+        .lines()
+        .skip_while(|line| line.trim() != "[Trash Info]")
+        .find_map(|line| line.strip_prefix("DeletionDate="))?;
+    let (date, time) = value.trim().split_once('T')?;
+    let mut date = date.splitn(3, '-').map(|part| part.parse::<i32>().ok());
+    let time = time.split(['.', 'Z', '+']).next()?;
+    let mut time = time.splitn(3, ':').map(|part| part.parse::<i32>().ok());
+
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = date.next()?? - 1900;
+    tm.tm_mon = date.next()?? - 1;
+    tm.tm_mday = date.next()??;
+    tm.tm_hour = time.next()??;
+    tm.tm_min = time.next()??;
+    tm.tm_sec = time.next()??;
+    tm.tm_isdst = -1; // 由 mktime 判断夏令时
+    let secs = unsafe { libc::mktime(&mut tm) };
+    u64::try_from(secs).ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+fn percent_decode(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+fn is_sticky_dir(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.is_dir() && metadata.permissions().mode() & 0o1000 != 0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    fn info(date: &str) -> String {
+        format!("[Trash Info]\nPath=/home/user/file\nDeletionDate={}\n", date)
+    }
+
+    fn secs(time: SystemTime) -> i64 {
+        time.duration_since(UNIX_EPOCH).unwrap().as_secs() as i64
+    }
+
+    #[test]
+    fn deletion_date_is_local_time() {
+        let midnight = deletion_date(&info("2024-01-15T00:00:00")).unwrap();
+        let later = deletion_date(&info("2024-01-15T12:34:56")).unwrap();
+        let next_day = deletion_date(&info("2024-01-16T00:00:00")).unwrap();
+        assert_eq!(secs(later) - secs(midnight), 12 * 3600 + 34 * 60 + 56);
+        assert_eq!(secs(next_day) - secs(midnight), 24 * 3600);
+        // 本地时间与 UTC 相差不超过 14 小时（2024-01-15T00:00:00Z 为 1705276800）
+        assert!((secs(midnight) - 1705276800).abs() <= 14 * 3600);
+        // 部分实现会写入小数秒或时区后缀，只取到秒
+        assert_eq!(deletion_date(&info("2024-01-15T12:34:56.789")), Some(later));
+    }
+
+    #[test]
+    fn deletion_date_requires_trash_info_section() {
+        assert!(deletion_date("DeletionDate=2024-01-15T00:00:00\n[Trash Info]\n").is_none());
+        assert!(deletion_date(&info("2024-01-15")).is_none());
+        assert!(deletion_date(&info("2024-01-xxT00:00:00")).is_none());
+        assert!(deletion_date("[Trash Info]\nPath=/a\n").is_none());
+    }
+
+    #[test]
+    fn purge_dir_removes_old_items_and_updates_sizes() {
+        let temp = tempfile::tempdir().unwrap();
+        let trash = temp.path();
+        fs::create_dir_all(trash.join("files/old dir")).unwrap();
+        fs::create_dir_all(trash.join("info")).unwrap();
+        fs::write(trash.join("files/old dir/a"), "12345").unwrap();
+        fs::write(trash.join("files/old.txt"), "123").unwrap();
+        fs::write(trash.join("files/new.txt"), "1").unwrap();
+        fs::write(trash.join("info/old dir.trashinfo"), info("2000-01-01T00:00:00")).unwrap();
+        fs::write(trash.join("info/old.txt.trashinfo"), info("2000-01-01T00:00:00")).unwrap();
+        fs::write(trash.join("info/new.txt.trashinfo"), info("2999-01-01T00:00:00")).unwrap();
+        // 没有对应内容的信息文件
+        fs::write(trash.join("info/missing.trashinfo"), info("2000-01-01T00:00:00")).unwrap();
+        fs::write(trash.join(DIRECTORY_SIZES), "5 0 old%20dir\n9 0 kept\n").unwrap();
+        fs::create_dir(trash.join("files/kept")).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        let retention = Retention { min_age_days: 30, ..Retention::default() };
+        assert_eq!(purge_dir(trash, &retention, &sender).unwrap(), 8);
+        assert!(receiver.try_recv().is_err());
+
+        assert!(!trash.join("files/old dir").exists());
+        assert!(!trash.join("files/old.txt").exists());
+        assert!(!trash.join("info/missing.trashinfo").exists());
+        assert!(trash.join("files/new.txt").exists());
+        assert!(trash.join("info/new.txt.trashinfo").exists());
+        assert_eq!(fs::read_to_string(trash.join(DIRECTORY_SIZES)).unwrap(), "9 0 kept\n");
+    }
+
+    #[test]
+    fn purge_dir_skips_dot_names() {
+        let temp = tempfile::tempdir().unwrap();
+        let trash = temp.path().join("Trash");
+        fs::create_dir_all(trash.join("files/item")).unwrap();
+        fs::create_dir_all(trash.join("info")).unwrap();
+        for name in [".trashinfo", "..trashinfo", "...trashinfo"] {
+            fs::write(trash.join("info").join(name), info("2000-01-01T00:00:00")).unwrap();
+        }
+
+        let (sender, receiver) = mpsc::channel();
+        assert_eq!(purge_dir(&trash, &Retention::default(), &sender).unwrap(), 0);
+        assert!(receiver.try_recv().is_err());
+        assert!(trash.join("files/item").is_dir());
+        assert!(trash.join("info/..trashinfo").exists());
+        assert!(trash.join("info/...trashinfo").exists());
+    }
+
+    #[test]
+    fn purge_dir_without_info_is_empty() {
+        let temp = tempfile::tempdir().unwrap();
+        let (sender, _receiver) = mpsc::channel();
+        assert_eq!(purge_dir(temp.path(), &Retention::default(), &sender).unwrap(), 0);
+        // info 不是目录时报告错误，由 purge 转为 RecycleBinFailed
+        fs::write(temp.path().join("info"), "").unwrap();
+        assert!(purge_dir(temp.path(), &Retention::default(), &sender).is_err());
+    }
+
+    #[test]
+    fn trash_dirs_start_with_data_home() {
+        let temp = tempfile::tempdir().unwrap();
+        let mounts = Mounts::load(&crate::config::ScanConfig::default());
+        // 回收站还不存在时跳过
+        assert!(!trash_dirs(Some(temp.path()), &mounts).contains(&temp.path().join("Trash")));
+
+        fs::create_dir(temp.path().join("Trash")).unwrap();
+        assert_eq!(trash_dirs(Some(temp.path()), &mounts).first(), Some(&temp.path().join("Trash")));
+
+        // 符号链接形式的回收站不处理
+        let linked = tempfile::tempdir().unwrap();
+        std::os::unix::fs::symlink(temp.path().join("Trash"), linked.path().join("Trash")).unwrap();
+        assert!(!trash_dirs(Some(linked.path()), &mounts).contains(&linked.path().join("Trash")));
+    }
+
+    #[test]
+    fn percent_decode_names() {
+        assert_eq!(percent_decode("old%20dir").as_deref(), Some("old dir"));
+        assert_eq!(percent_decode("%E4%B8%AD").as_deref(), Some("中"));
+        assert_eq!(percent_decode("bad%2"), None);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    config::{AppPolicy, RuleConfig, XdgCacheConfig},
+    rules::RuleList,
+};
+
+// 内置的应用目录策略：None 表示整个目录保留，Some(天数) 表示清理超过该天数的文件（0 为全部清理）。
+// 字体和着色器缓存重建很慢；浏览器缓存由各浏览器目标处理，它们会在浏览器运行时跳过
+const BUILTIN_POLICIES: &[(&str, Option<u64>)] = &[
+    ("fontconfig", None),
+    ("mesa_shader_cache", None),
+    ("nvidia", None),
+    ("ibus", None),
+    ("thumbnails", Some(30)),
+    ("mozilla", None),
+    ("google-chrome", None),
+    ("google-chrome-beta", None),
+    ("google-chrome-unstable", None),
+    ("chromium", None),
+    ("BraveSoftware", None),
+    ("microsoft-edge", None),
+    ("microsoft-edge-beta", None),
+    ("vivaldi", None),
+    ("opera", Some(0)),
+];
+
+// 每个应用目录一条规则，配置中的策略覆盖内置策略；其余应用目录按 default_min_age_days 清理，
+// 缓存目录下的顶层文件不处理
+pub fn rules(config: &XdgCacheConfig) -> RuleList {
+    let mut policies: Vec<(String, AppPolicy)> = BUILTIN_POLICIES
+        .iter()
+        .filter(|(app, _)| !config.apps.contains_key(*app))
+        .map(|(app, age)| (app.to_string(), AppPolicy { keep: age.is_none(), min_age_days: age.unwrap_or(0) }))
+        .collect();
+    policies.extend(config.apps.iter().map(|(app, policy)| (app.clone(), policy.clone())));
+
+    let mut rules = Vec::new();
+    let mut exclude = Vec::new();
+    for (app, policy) in policies {
+        // 只匹配应用目录中的内容，应用目录本身保留
+        let app_dir = format!("/{}/", globset::escape(&app));
+        if policy.keep {
+            exclude.push(app_dir);
+        } else {
+            rules.push(rule(format!("xdg-{}", app), format!("{}*", app_dir), policy.min_age_days));
+        }
+    }
+    rules.push(rule("xdg-default".to_string(), "/*/*".to_string(), config.default_min_age_days));
+    if !exclude.is_empty() {
+        rules.push(RuleConfig { name: None, include: Vec::new(), exclude, min_age_days: 0, min_size_kb: 0 });
+    }
+
+    RuleList::try_from(rules).unwrap_or_default()
+}
+
+fn rule(name: String, pattern: String, min_age_days: u64) -> RuleConfig {
+    RuleConfig { name: Some(name), include: vec![pattern], exclude: Vec::new(), min_age_days, min_size_kb: 0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+    use crate::{Target, config::ScanConfig, mounts::Mounts, plan_directory};
+
+    fn write(path: &Path, days_old: u32) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, "data").unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(24 * 3600) * days_old;
+        fs::File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+    }
+
+    fn planned(cache: &Path, config: &XdgCacheConfig) -> Vec<String> {
+        let target = Target::new("xdg-cache", cache).with_rules(rules(config));
+        let mut paths: Vec<String> = plan_directory(&target, &Mounts::load(&ScanConfig::default()))
+            .iter()
+            .map(|entry| entry.path.strip_prefix(cache).unwrap().to_string_lossy().replace('\\', "/"))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn builtin_policies_and_default_age() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = temp.path();
+        write(&cache.join("fontconfig/cache-1"), 100);
+        write(&cache.join("thumbnails/large/old.png"), 40);
+        write(&cache.join("thumbnails/large/new.png"), 1);
+        write(&cache.join("opera/fresh"), 0);
+        write(&cache.join("someapp/old.bin"), 40);
+        write(&cache.join("someapp/new.bin"), 1);
+        // 缓存目录下的顶层文件不处理
+        write(&cache.join("stray-file"), 100);
+
+        // 字体缓存整个保留，opera 全部清理，其余超过各自的天数才清理
+        assert_eq!(planned(cache, &XdgCacheConfig::default()), ["opera/fresh", "someapp/old.bin", "thumbnails/large/old.png"]);
+    }
+
+    #[test]
+    fn configured_policies_override_builtin() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = temp.path();
+        write(&cache.join("fontconfig/cache-1"), 100);
+        write(&cache.join("opera/fresh"), 0);
+        write(&cache.join("someapp/old.bin"), 10);
+        write(&cache.join("pinned/old.bin"), 100);
+
+        let mut config = XdgCacheConfig { default_min_age_days: 7, ..XdgCacheConfig::default() };
+        config.apps.insert("fontconfig".to_string(), AppPolicy { keep: false, min_age_days: 0 });
+        config.apps.insert("opera".to_string(), AppPolicy { keep: true, min_age_days: 0 });
+        config.apps.insert("pinned".to_string(), AppPolicy { keep: true, min_age_days: 0 });
+        assert_eq!(planned(cache, &config), ["fontconfig/cache-1", "someapp/old.bin"]);
+    }
+}